@@ -0,0 +1,369 @@
+use crate::helpers::LOG_TARGET;
+use moon_error::{map_io_to_fs_error, map_json_to_error, MoonError};
+use moon_logger::{color, trace};
+use moon_utils::path;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use tar::{Archive, Builder, Header};
+use zstd::stream::{read::Decoder, write::Encoder};
+
+const MANIFEST_NAME: &str = "manifest.json";
+
+const FILES_PREFIX: &str = "files/";
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputManifestFile {
+    pub mode: u32,
+
+    /// Relative from the project root, using forward slashes.
+    pub path: String,
+
+    pub size: u64,
+}
+
+/// Stored as the first entry of every output archive, and used to
+/// verify the contents when they are restored.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputManifest {
+    pub files: Vec<OutputManifestFile>,
+
+    /// Declared outputs (relative from the project root)
+    /// that existed when the archive was created.
+    pub outputs: Vec<String>,
+}
+
+impl OutputManifest {
+    /// Returns true if every output was archived.
+    pub fn contains_outputs(&self, outputs: &[String]) -> bool {
+        outputs.iter().all(|output| self.outputs.contains(output))
+    }
+}
+
+#[cfg(unix)]
+fn get_file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+
+    metadata.permissions().mode() & 0o7777
+}
+
+#[cfg(windows)]
+fn get_file_mode(metadata: &fs::Metadata) -> u32 {
+    if metadata.permissions().readonly() {
+        0o444
+    } else {
+        0o644
+    }
+}
+
+#[cfg(unix)]
+fn set_file_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(windows)]
+fn set_file_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+/// Convert a path relative from the root into a forward slash string,
+/// so that archives are portable between operating systems.
+pub fn to_relative_string(root: &Path, path: &Path) -> Option<String> {
+    let rel_path = path.strip_prefix(root).ok()?;
+
+    Some(path::standardize_separators(rel_path.to_str()?))
+}
+
+fn collect_files(
+    root: &Path,
+    path: &Path,
+    files: &mut Vec<(PathBuf, OutputManifestFile)>,
+) -> io::Result<()> {
+    let metadata = fs::metadata(path)?;
+
+    if metadata.is_dir() {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        // Sort for deterministic archives
+        entries.sort();
+
+        for entry in entries {
+            collect_files(root, &entry, files)?;
+        }
+    } else if let Some(rel_path) = to_relative_string(root, path) {
+        files.push((
+            path.to_path_buf(),
+            OutputManifestFile {
+                mode: get_file_mode(&metadata),
+                path: rel_path,
+                size: metadata.len(),
+            },
+        ));
+    }
+
+    Ok(())
+}
+
+/// Create a zstd compressed tarball at the archive path, containing a manifest
+/// and all files within the provided output paths (relative from the root).
+/// Outputs that do not exist are omitted from the archive and its manifest.
+pub fn create_archive(
+    archive_path: &Path,
+    root: &Path,
+    output_paths: &[PathBuf],
+) -> Result<OutputManifest, MoonError> {
+    trace!(
+        target: LOG_TARGET,
+        "Archiving outputs to {}",
+        color::path(archive_path)
+    );
+
+    let handle_error = |e| map_io_to_fs_error(e, archive_path.to_path_buf());
+    let mut manifest = OutputManifest::default();
+    let mut files = vec![];
+
+    for output_path in output_paths {
+        if !output_path.exists() {
+            continue;
+        }
+
+        if let Some(output) = to_relative_string(root, output_path) {
+            collect_files(root, output_path, &mut files)
+                .map_err(|e| map_io_to_fs_error(e, output_path.to_path_buf()))?;
+
+            manifest.outputs.push(output);
+        }
+    }
+
+    manifest.outputs.sort();
+    manifest.files = files.iter().map(|(_, file)| file.clone()).collect();
+
+    // Write to a temporary file first and then rename,
+    // so that a partially written archive is never read
+    let temp_path = archive_path.with_extension("tmp");
+    let encoder =
+        Encoder::new(File::create(&temp_path).map_err(handle_error)?, 0).map_err(handle_error)?;
+    let mut builder = Builder::new(encoder);
+
+    let manifest_data = serde_json::to_vec(&manifest)
+        .map_err(|e| map_json_to_error(e, archive_path.to_path_buf()))?;
+    let mut header = Header::new_gnu();
+
+    header.set_size(manifest_data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+
+    builder
+        .append_data(&mut header, MANIFEST_NAME, manifest_data.as_slice())
+        .map_err(handle_error)?;
+
+    for (abs_path, file) in &files {
+        builder
+            .append_path_with_name(abs_path, format!("{}{}", FILES_PREFIX, file.path))
+            .map_err(|e| map_io_to_fs_error(e, abs_path.to_path_buf()))?;
+    }
+
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(handle_error)?;
+
+    fs::rename(&temp_path, archive_path).map_err(handle_error)?;
+
+    Ok(manifest)
+}
+
+/// Read only the manifest from an archive, without extracting any files.
+pub fn read_manifest(archive_path: &Path) -> Result<OutputManifest, MoonError> {
+    let handle_error = |e| map_io_to_fs_error(e, archive_path.to_path_buf());
+    let decoder =
+        Decoder::new(File::open(archive_path).map_err(handle_error)?).map_err(handle_error)?;
+    let mut archive = Archive::new(decoder);
+
+    // The manifest is always the first entry
+    if let Some(entry) = archive.entries().map_err(handle_error)?.next() {
+        let mut entry = entry.map_err(handle_error)?;
+
+        if entry.path().map_err(handle_error)?.to_str() == Some(MANIFEST_NAME) {
+            let mut data = String::new();
+
+            entry.read_to_string(&mut data).map_err(handle_error)?;
+
+            return serde_json::from_str(&data)
+                .map_err(|e| map_json_to_error(e, archive_path.to_path_buf()));
+        }
+    }
+
+    Err(handle_error(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "Output archive is missing a manifest.",
+    )))
+}
+
+/// Extract all files from an archive into the root, and verify
+/// their sizes against the manifest, and restore their modes.
+pub fn extract_archive(archive_path: &Path, root: &Path) -> Result<OutputManifest, MoonError> {
+    trace!(
+        target: LOG_TARGET,
+        "Extracting outputs from {} to {}",
+        color::path(archive_path),
+        color::path(root)
+    );
+
+    let handle_error = |e| map_io_to_fs_error(e, archive_path.to_path_buf());
+    let invalid_data = |message: &str| {
+        handle_error(io::Error::new(
+            io::ErrorKind::InvalidData,
+            message.to_owned(),
+        ))
+    };
+
+    let manifest = read_manifest(archive_path)?;
+    let decoder =
+        Decoder::new(File::open(archive_path).map_err(handle_error)?).map_err(handle_error)?;
+    let mut archive = Archive::new(decoder);
+
+    for entry in archive.entries().map_err(handle_error)? {
+        let mut entry = entry.map_err(handle_error)?;
+        let entry_path = entry.path().map_err(handle_error)?.to_path_buf();
+
+        let rel_path = match entry_path.to_str() {
+            Some(p) if p.starts_with(FILES_PREFIX) => p.trim_start_matches(FILES_PREFIX),
+            _ => continue,
+        };
+
+        // Never write outside of the root
+        if Path::new(rel_path)
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(invalid_data("Output archive contains an invalid path."));
+        }
+
+        let dest_path = root.join(rel_path);
+
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| map_io_to_fs_error(e, parent.to_path_buf()))?;
+        }
+
+        entry
+            .unpack(&dest_path)
+            .map_err(|e| map_io_to_fs_error(e, dest_path.clone()))?;
+    }
+
+    for file in &manifest.files {
+        let dest_path = root.join(&file.path);
+        let handle_dest_error = |e| map_io_to_fs_error(e, dest_path.clone());
+
+        let size = fs::metadata(&dest_path).map_err(handle_dest_error)?.len();
+
+        if size != file.size {
+            return Err(invalid_data(&format!(
+                "Output {} does not match the size in the manifest.",
+                file.path
+            )));
+        }
+
+        set_file_mode(&dest_path, file.mode).map_err(handle_dest_error)?;
+    }
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+
+    #[test]
+    fn creates_and_extracts_archives() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let archive_path = dir.path().join("out.tar.zst");
+
+        dir.child("project/file.js").write_str("file").unwrap();
+        dir.child("project/lib/a.js").write_str("aaa").unwrap();
+        dir.child("project/lib/b.js").write_str("bb").unwrap();
+
+        let root = dir.path().join("project");
+        let manifest = create_archive(
+            &archive_path,
+            &root,
+            &[root.join("file.js"), root.join("lib"), root.join("missing")],
+        )
+        .unwrap();
+
+        assert_eq!(
+            manifest.outputs,
+            vec!["file.js".to_owned(), "lib".to_owned()]
+        );
+        assert_eq!(
+            manifest
+                .files
+                .iter()
+                .map(|f| (f.path.as_str(), f.size))
+                .collect::<Vec<_>>(),
+            vec![("file.js", 4), ("lib/a.js", 3), ("lib/b.js", 2)]
+        );
+        assert_eq!(read_manifest(&archive_path).unwrap(), manifest);
+
+        let dest = dir.path().join("dest");
+
+        assert_eq!(extract_archive(&archive_path, &dest).unwrap(), manifest);
+        assert_eq!(fs::read_to_string(dest.join("file.js")).unwrap(), "file");
+        assert_eq!(fs::read_to_string(dest.join("lib/a.js")).unwrap(), "aaa");
+        assert_eq!(fs::read_to_string(dest.join("lib/b.js")).unwrap(), "bb");
+
+        dir.close().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn preserves_file_modes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = assert_fs::TempDir::new().unwrap();
+        let archive_path = dir.path().join("out.tar.zst");
+
+        dir.child("project/bin.sh").write_str("#!/bin/sh").unwrap();
+
+        let root = dir.path().join("project");
+
+        fs::set_permissions(root.join("bin.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        create_archive(&archive_path, &root, &[root.join("bin.sh")]).unwrap();
+
+        let dest = dir.path().join("dest");
+
+        extract_archive(&archive_path, &dest).unwrap();
+
+        assert_eq!(
+            fs::metadata(dest.join("bin.sh"))
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o777,
+            0o755
+        );
+
+        dir.close().unwrap();
+    }
+
+    #[test]
+    fn checks_for_outputs() {
+        let manifest = OutputManifest {
+            files: vec![],
+            outputs: vec!["file.js".to_owned(), "lib".to_owned()],
+        };
+
+        assert!(manifest.contains_outputs(&[]));
+        assert!(manifest.contains_outputs(&["lib".to_owned()]));
+        assert!(!manifest.contains_outputs(&["file.js".to_owned(), "other".to_owned()]));
+    }
+}
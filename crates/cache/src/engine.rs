@@ -1,13 +1,54 @@
-use crate::helpers::{is_writable, LOG_TARGET};
+use crate::archive::{create_archive, extract_archive, read_manifest, to_relative_string};
+use crate::helpers::{is_readable, is_writable, LOG_TARGET};
 use crate::items::{CacheItem, ProjectsState, RunTargetState, WorkspaceState};
+use crate::lock::CacheLock;
+use crate::remote::RemoteCache;
 use crate::runfiles::CacheRunfile;
+use filetime::{set_file_mtime, FileTime};
 use moon_config::constants::CONFIG_DIRNAME;
-use moon_error::MoonError;
+use moon_error::{map_io_to_fs_error, MoonError};
 use moon_logger::{color, debug, trace};
 use moon_utils::fs;
+use moon_utils::time::chrono::Utc;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::io;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Max number of logs that are kept for each action. Older logs are deleted first.
+const MAX_RUN_LOGS: usize = 10;
+
+fn remove_lock_file(lock: CacheLock) {
+    // Remove while the lock is still held, so that another process does not
+    // acquire it in between. Processes that were waiting on the removed file
+    // will notice and lock the path again (see `CacheLock`). This may fail on
+    // Windows, in which case the file is left behind, which is harmless.
+    let _ = std::fs::remove_file(&lock.path);
+}
+
+// Archiving is synchronous (and compression is CPU heavy), so run it on a blocking
+// thread, instead of blocking the runtime while other actions are running
+async fn run_blocking<T, F>(func: F) -> Result<T, MoonError>
+where
+    F: FnOnce() -> Result<T, MoonError> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(func)
+        .await
+        .map_err(|e| MoonError::Unknown(io::Error::new(io::ErrorKind::Other, e)))?
+}
+
+// Write to a temporary file first and then rename, so that an interrupted
+// download never leaves a partially written file to be hydrated later
+async fn write_atomic(path: &Path, data: Vec<u8>) -> Result<(), MoonError> {
+    let temp_path = path.with_extension("tmp");
+
+    fs::write(&temp_path, data).await?;
+    fs::rename(&temp_path, path).await?;
+
+    Ok(())
+}
 
 pub struct CacheEngine {
     /// The `.moon/cache` directory relative to workspace root.
@@ -22,6 +63,9 @@ pub struct CacheEngine {
 
     /// The `.moon/cache/out` directory. Stores task output.
     pub outputs_dir: PathBuf,
+
+    /// Remote cache to share hashes and outputs with, when configured.
+    pub remote: Option<RemoteCache>,
 }
 
 impl CacheEngine {
@@ -46,6 +90,7 @@ impl CacheEngine {
             hashes_dir,
             runs_dir,
             outputs_dir,
+            remote: None,
         })
     }
 
@@ -86,6 +131,147 @@ impl CacheEngine {
         .await
     }
 
+    /// Delete hashes (and their outputs) that have not been used within the max age,
+    /// and then the least recently used hashes until the total size is below the max size.
+    /// Outputs without a hash are always deleted. Returns the number of hashes deleted,
+    /// and the number of bytes freed.
+    pub async fn clean_stale_cache(
+        &self,
+        max_age: Duration,
+        max_size: Option<u64>,
+    ) -> Result<(usize, u64), MoonError> {
+        if !is_writable() {
+            return Ok((0, 0));
+        }
+
+        let mut entries = vec![];
+
+        for entry in fs::read_dir(&self.hashes_dir).await? {
+            let path = entry.path();
+            let extension = path.extension().unwrap_or_default();
+
+            // Delete locks for hashes that were never saved (the run failed),
+            // as long as another process is not currently using them
+            if extension == "lock" {
+                if !path.with_extension("json").exists() {
+                    if let Some(lock) = CacheLock::try_acquire(path.clone())? {
+                        remove_lock_file(lock);
+                    }
+                }
+
+                continue;
+            }
+
+            if extension != "json" {
+                continue;
+            }
+
+            let hash = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) => stem.to_owned(),
+                None => continue,
+            };
+
+            let metadata = fs::metadata(&path).await?;
+            let last_used = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            let mut size = metadata.len();
+            let archive_path = self.get_output_archive_path(&hash);
+
+            if archive_path.exists() {
+                size += fs::metadata(&archive_path).await?.len();
+            }
+
+            entries.push((hash, last_used, size));
+        }
+
+        // Least recently used first
+        entries.sort_by(|a, b| a.1.cmp(&b.1));
+
+        let now = SystemTime::now();
+        let mut total_size: u64 = entries.iter().map(|e| e.2).sum();
+        let mut deleted_count = 0;
+        let mut freed_bytes = 0;
+
+        for (hash, last_used, size) in entries {
+            let is_stale = match now.duration_since(last_used) {
+                Ok(age) => age > max_age,
+                Err(_) => false,
+            };
+            let is_oversized = match max_size {
+                Some(max) => total_size > max,
+                None => false,
+            };
+
+            if !is_stale && !is_oversized {
+                break;
+            }
+
+            // Another process is using this hash, so leave it alone
+            let lock = match CacheLock::try_acquire(self.get_hash_lock_path(&hash))? {
+                Some(lock) => lock,
+                None => {
+                    trace!(
+                        target: LOG_TARGET,
+                        "Skipping hash {} as it's locked by another process",
+                        color::symbol(&hash),
+                    );
+
+                    continue;
+                }
+            };
+
+            trace!(
+                target: LOG_TARGET,
+                "Cleaning hash {} ({})",
+                color::symbol(&hash),
+                if is_stale { "stale" } else { "over size limit" }
+            );
+
+            self.delete_hash(&hash).await?;
+
+            remove_lock_file(lock);
+
+            total_size -= size;
+            freed_bytes += size;
+            deleted_count += 1;
+        }
+
+        // Delete outputs that no longer have a hash, for example,
+        // partially written archives, or legacy output folders
+        for entry in fs::read_dir(&self.outputs_dir).await? {
+            let path = entry.path();
+            let name = entry.file_name();
+            let hash = match name.to_str() {
+                Some(name) => name.split('.').next().unwrap_or_default(),
+                None => continue,
+            };
+
+            if self.hashes_dir.join(format!("{}.json", hash)).exists() {
+                continue;
+            }
+
+            // Archives that are currently being written are also locked
+            let lock = match CacheLock::try_acquire(self.get_hash_lock_path(hash))? {
+                Some(lock) => lock,
+                None => continue,
+            };
+
+            if path.is_dir() {
+                for file in fs::read_dir_all(&path).await? {
+                    freed_bytes += fs::metadata(&file.path()).await?.len();
+                }
+
+                fs::remove_dir_all(&path).await?;
+            } else {
+                freed_bytes += fs::metadata(&path).await?.len();
+                fs::remove_file(&path).await?;
+            }
+
+            remove_lock_file(lock);
+        }
+
+        Ok((deleted_count, freed_bytes))
+    }
+
     pub async fn create_runfile<T: DeserializeOwned + Serialize>(
         &self,
         project_id: &str,
@@ -93,254 +279,1239 @@ impl CacheEngine {
     ) -> Result<CacheRunfile, MoonError> {
         let path: PathBuf = [project_id, "runfile.json"].iter().collect();
 
-        CacheRunfile::load(self.runs_dir.join(path), data).await
-    }
+        CacheRunfile::load(self.runs_dir.join(path), data).await
+    }
+
+    pub async fn delete_hash(&self, hash: &str) -> Result<(), MoonError> {
+        if is_writable() {
+            let path = self.hashes_dir.join(format!("{}.json", hash));
+
+            trace!(target: "moon:cache:hash", "Deleting hash {}", color::path(&path));
+
+            // Remove the hash file itself
+            fs::remove_file(&path).await?;
+
+            // And the outputs archive with the hash
+            fs::remove_file(&self.get_output_archive_path(hash)).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete_runfiles(&self) -> Result<(), MoonError> {
+        let entries = fs::read_dir(&self.runs_dir).await?;
+
+        for entry in entries {
+            let path = entry.path();
+
+            if path.is_dir() {
+                let runfile = path.join("runfile.json");
+
+                trace!(target: "moon:cache:runfile", "Deleting runfile {}", color::path(&runfile));
+
+                fs::remove_file(&runfile).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Return the path to the most recent log file for the provided target ID
+    /// (or action key), if one has been written.
+    pub async fn get_last_run_log(&self, id: &str) -> Result<Option<PathBuf>, MoonError> {
+        Ok(self.get_run_logs(id).await?.pop())
+    }
+
+    /// Return all log files for the provided target ID (or action key),
+    /// sorted from oldest to newest.
+    async fn get_run_logs(&self, id: &str) -> Result<Vec<PathBuf>, MoonError> {
+        let dir = self.runs_dir.join(id.replace(':', "/"));
+
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut logs = fs::read_dir(&dir)
+            .await?
+            .into_iter()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "log"))
+            .collect::<Vec<_>>();
+
+        // File names are timestamps, so they sort chronologically
+        logs.sort();
+
+        Ok(logs)
+    }
+
+    /// Write the profile (a Chrome trace) of the last action runner run
+    /// to `.moon/cache/runProfile.json`.
+    pub async fn write_run_profile<T>(&self, profile: &T) -> Result<PathBuf, MoonError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let path = self.dir.join("runProfile.json");
+
+        trace!(
+            target: LOG_TARGET,
+            "Writing run profile {}",
+            color::path(&path)
+        );
+
+        fs::write_json(&path, profile, false).await?;
+
+        Ok(path)
+    }
+
+    /// Write the report of the last action runner run to `.moon/cache/runReport.json`.
+    /// Like logs, the report is always written, regardless of `MOON_CACHE`.
+    pub async fn write_run_report<T>(&self, report: &T) -> Result<PathBuf, MoonError>
+    where
+        T: ?Sized + Serialize,
+    {
+        let path = self.dir.join("runReport.json");
+
+        trace!(
+            target: LOG_TARGET,
+            "Writing run report {}",
+            color::path(&path)
+        );
+
+        fs::write_json(&path, report, true).await?;
+
+        Ok(path)
+    }
+
+    /// Write a timestamped log file for a single run of an action, into
+    /// `.moon/cache/runs/<project>/<task>` for targets, or `.moon/cache/runs/<key>`
+    /// for other actions. Logs are always written, regardless of `MOON_CACHE`,
+    /// so that they can be inspected after a failure.
+    pub async fn write_run_log(&self, id: &str, contents: &str) -> Result<PathBuf, MoonError> {
+        let dir = self.runs_dir.join(id.replace(':', "/"));
+        let path = dir.join(format!(
+            "{}.log",
+            Utc::now().format("%Y-%m-%dT%H-%M-%S%.3fZ")
+        ));
+
+        trace!(target: "moon:cache:log", "Writing run log {}", color::path(&path));
+
+        fs::create_dir_all(&dir).await?;
+        fs::write(&path, contents).await?;
+
+        // Only keep the most recent logs, so that they don't grow indefinitely
+        let logs = self.get_run_logs(id).await?;
+
+        if logs.len() > MAX_RUN_LOGS {
+            for log in &logs[..logs.len() - MAX_RUN_LOGS] {
+                trace!(target: "moon:cache:log", "Deleting run log {}", color::path(log));
+
+                fs::remove_file(log).await?;
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Download the hash manifest and outputs archive from the remote cache,
+    /// and write them into `.moon/cache`. Returns false on a remote cache miss.
+    pub async fn download_from_remote(&self, hash: &str) -> Result<bool, MoonError> {
+        let remote = match &self.remote {
+            Some(remote) if is_readable() => remote,
+            _ => return Ok(false),
+        };
+
+        let manifest = match remote.download_hash(hash).await? {
+            Some(bytes) => bytes,
+            None => return Ok(false),
+        };
+
+        let outputs = match remote.download_outputs(hash).await? {
+            Some(bytes) => bytes,
+            None => return Ok(false),
+        };
+
+        trace!(
+            target: LOG_TARGET,
+            "Downloaded hash {} from remote cache {}",
+            color::symbol(hash),
+            color::url(&remote.host)
+        );
+
+        // The archive is written first, so that the existence
+        // of a hash implies the existence of its outputs
+        write_atomic(&self.get_output_archive_path(hash), outputs).await?;
+        write_atomic(&self.hashes_dir.join(format!("{}.json", hash)), manifest).await?;
+
+        Ok(true)
+    }
+
+    /// Upload the hash manifest and outputs archive for the provided hash
+    /// to the remote cache. Outputs are uploaded first, so that the existence
+    /// of a remote hash implies the existence of its outputs.
+    pub async fn upload_to_remote(&self, hash: &str) -> Result<(), MoonError> {
+        let remote = match &self.remote {
+            Some(remote) if is_writable() => remote,
+            _ => return Ok(()),
+        };
+
+        let manifest_path = self.hashes_dir.join(format!("{}.json", hash));
+        let archive_path = self.get_output_archive_path(hash);
+
+        if !manifest_path.exists() || !archive_path.exists() {
+            return Ok(());
+        }
+
+        trace!(
+            target: LOG_TARGET,
+            "Uploading hash {} to remote cache {}",
+            color::symbol(hash),
+            color::url(&remote.host)
+        );
+
+        remote
+            .upload_outputs(hash, fs::read(&archive_path).await?)
+            .await?;
+
+        remote
+            .upload_hash(hash, fs::read(&manifest_path).await?)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Return the path to the lock file for the provided hash.
+    pub fn get_hash_lock_path(&self, hash: &str) -> PathBuf {
+        self.hashes_dir.join(format!("{}.lock", hash))
+    }
+
+    /// Return the path to the outputs archive for the provided hash.
+    pub fn get_output_archive_path(&self, hash: &str) -> PathBuf {
+        self.outputs_dir.join(format!("{}.tar.zst", hash))
+    }
+
+    /// Restore task outputs from `.moon/cache/out/<hash>.tar.zst` back into their
+    /// original location. Returns false (and restores nothing) if the archive
+    /// does not exist, or any output is missing from it.
+    pub async fn hydrate_from_out(
+        &self,
+        hash: &str,
+        dest_root: &Path,
+        dest_paths: &[PathBuf],
+    ) -> Result<bool, MoonError> {
+        if dest_paths.is_empty() {
+            return Ok(true);
+        }
+
+        let archive_path = self.get_output_archive_path(hash);
+
+        if !archive_path.exists() {
+            trace!(
+                target: LOG_TARGET,
+                "Output archive {} does not exist, unable to hydrate",
+                color::path(&archive_path)
+            );
+
+            return Ok(false);
+        }
+
+        let mut outputs = vec![];
+
+        for dest_path in dest_paths {
+            match to_relative_string(dest_root, dest_path) {
+                Some(output) => outputs.push(output),
+                None => return Ok(false),
+            };
+        }
+
+        // Verify everything exists *before* touching the destination,
+        // so that we don't leave outputs in a partially hydrated state
+        let manifest_path = archive_path.clone();
+
+        if !run_blocking(move || read_manifest(&manifest_path))
+            .await?
+            .contains_outputs(&outputs)
+        {
+            trace!(
+                target: LOG_TARGET,
+                "Outputs are missing from archive {}, unable to hydrate",
+                color::path(&archive_path)
+            );
+
+            return Ok(false);
+        }
+
+        trace!(
+            target: LOG_TARGET,
+            "Hydrating outputs from {}",
+            color::path(&archive_path)
+        );
+
+        for dest_path in dest_paths {
+            if dest_path.is_dir() {
+                fs::remove_dir_all(dest_path).await?;
+            } else {
+                fs::remove_file(dest_path).await?;
+            }
+        }
+
+        let dest_root = dest_root.to_path_buf();
+
+        run_blocking(move || extract_archive(&archive_path, &dest_root)).await?;
+
+        Ok(true)
+    }
+
+    /// Archive task outputs (relative from the source root) into
+    /// `.moon/cache/out/<hash>.tar.zst`, along with a manifest.
+    pub async fn archive_task_outputs(
+        &self,
+        hash: &str,
+        source_root: &Path,
+        source_paths: &[PathBuf],
+    ) -> Result<(), MoonError> {
+        if is_writable() {
+            let archive_path = self.get_output_archive_path(hash);
+            let source_root = source_root.to_path_buf();
+            let source_paths = source_paths.to_vec();
+
+            run_blocking(move || create_archive(&archive_path, &source_root, &source_paths))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Acquire a lock for the provided hash, so that its manifest and outputs archive
+    /// are not written to (or cleaned) by multiple processes at the same time.
+    pub async fn lock_hash(&self, hash: &str) -> Result<CacheLock, MoonError> {
+        CacheLock::acquire(self.get_hash_lock_path(hash)).await
+    }
+
+    /// Acquire a lock for the projects state, so that concurrent processes
+    /// do not glob and write the state at the same time.
+    pub async fn lock_projects_state(&self) -> Result<CacheLock, MoonError> {
+        CacheLock::acquire(self.dir.join("projectsState.lock")).await
+    }
+
+    /// Acquire a lock for the provided target, so that its run state is not read
+    /// and written by multiple processes at the same time.
+    pub async fn lock_target(&self, target_id: &str) -> Result<CacheLock, MoonError> {
+        let path: PathBuf = [&target_id.replace(':', "/"), "lastRunState.lock"]
+            .iter()
+            .collect();
+
+        CacheLock::acquire(self.runs_dir.join(path)).await
+    }
+
+    /// Load the contents of a previously saved hash manifest.
+    /// Returns `None` if the hash does not exist.
+    pub async fn load_hash<T>(&self, hash: &str) -> Result<Option<T>, MoonError>
+    where
+        T: DeserializeOwned,
+    {
+        let path = self.hashes_dir.join(format!("{}.json", hash));
+
+        if is_readable() && path.exists() {
+            trace!(target: "moon:cache:hash", "Reading hash {}", color::path(&path));
+
+            return Ok(Some(fs::read_json(&path).await?));
+        }
+
+        Ok(None)
+    }
+
+    /// Mark the hash as recently used, so that it's not evicted
+    /// when cleaning the cache.
+    pub async fn touch_hash(&self, hash: &str) -> Result<(), MoonError> {
+        let path = self.hashes_dir.join(format!("{}.json", hash));
+
+        if is_writable() && path.exists() {
+            set_file_mtime(&path, FileTime::now())
+                .map_err(|e| map_io_to_fs_error(e, path.clone()))?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn save_hash<T>(&self, hash: &str, hasher: &T) -> Result<(), MoonError>
+    where
+        T: ?Sized + Serialize,
+    {
+        if is_writable() {
+            let path = self.hashes_dir.join(format!("{}.json", hash));
+
+            trace!(target: "moon:cache:hash", "Writing hash {}", color::path(&path));
+
+            fs::write_json(&path, &hasher, true).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::{run_with_env, to_millis};
+    use assert_fs::prelude::*;
+    use serial_test::serial;
+    use std::fs;
+
+    mod create {
+        use super::*;
+
+        #[tokio::test]
+        #[serial]
+        async fn creates_dirs() {
+            let dir = assert_fs::TempDir::new().unwrap();
+
+            CacheEngine::create(dir.path()).await.unwrap();
+
+            assert!(dir.path().join(".moon/cache").exists());
+            assert!(dir.path().join(".moon/cache/hashes").exists());
+            assert!(dir.path().join(".moon/cache/runs").exists());
+            assert!(dir.path().join(".moon/cache/out").exists());
+
+            dir.close().unwrap();
+        }
+    }
+
+    mod delete_runfiles {
+        use super::*;
+
+        #[tokio::test]
+        #[serial]
+        async fn deletes_dir() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            let runfile1 = cache
+                .create_runfile("123", &"content".to_owned())
+                .await
+                .unwrap();
+            let runfile2 = cache
+                .create_runfile("456", &"content".to_owned())
+                .await
+                .unwrap();
+
+            assert!(runfile1.path.exists());
+            assert!(runfile2.path.exists());
+
+            cache.delete_runfiles().await.unwrap();
+
+            assert!(!runfile1.path.exists());
+            assert!(!runfile2.path.exists());
+
+            dir.close().unwrap();
+        }
+    }
+
+    mod delete_hash {
+        use super::*;
+
+        #[tokio::test]
+        #[serial]
+        async fn deletes_files() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            dir.child(".moon/cache/hashes/abc123.json")
+                .write_str("{}")
+                .unwrap();
+
+            dir.child(".moon/cache/out/abc123.tar.zst")
+                .write_str("")
+                .unwrap();
+
+            let hash_file = cache.hashes_dir.join("abc123.json");
+            let out_file = cache.outputs_dir.join("abc123.tar.zst");
+
+            assert!(hash_file.exists());
+            assert!(out_file.exists());
+
+            cache.delete_hash("abc123").await.unwrap();
+
+            assert!(!hash_file.exists());
+            assert!(!out_file.exists());
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn doesnt_delete_if_cache_off() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            dir.child(".moon/cache/hashes/abc123.json")
+                .write_str("{}")
+                .unwrap();
+
+            dir.child(".moon/cache/out/abc123.tar.zst")
+                .write_str("")
+                .unwrap();
+
+            let hash_file = cache.hashes_dir.join("abc123.json");
+            let out_file = cache.outputs_dir.join("abc123.tar.zst");
+
+            assert!(hash_file.exists());
+            assert!(out_file.exists());
+
+            run_with_env("off", || cache.delete_hash("abc123"))
+                .await
+                .unwrap();
+
+            assert!(hash_file.exists());
+            assert!(out_file.exists());
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn doesnt_delete_if_cache_readonly() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            dir.child(".moon/cache/hashes/abc123.json")
+                .write_str("{}")
+                .unwrap();
+
+            dir.child(".moon/cache/out/abc123.tar.zst")
+                .write_str("")
+                .unwrap();
+
+            let hash_file = cache.hashes_dir.join("abc123.json");
+            let out_file = cache.outputs_dir.join("abc123.tar.zst");
+
+            assert!(hash_file.exists());
+            assert!(out_file.exists());
+
+            run_with_env("read", || cache.delete_hash("abc123"))
+                .await
+                .unwrap();
+
+            assert!(hash_file.exists());
+            assert!(out_file.exists());
+
+            dir.close().unwrap();
+        }
+    }
+
+    mod clean_stale_cache {
+        use super::*;
+
+        const DAY: u64 = 86400;
+
+        fn create_hash(dir: &assert_fs::TempDir, hash: &str, size: usize, age: u64) {
+            let manifest = dir.child(format!(".moon/cache/hashes/{}.json", hash));
+            let archive = dir.child(format!(".moon/cache/out/{}.tar.zst", hash));
+
+            manifest.write_str("{}").unwrap();
+            archive.write_str(&"x".repeat(size)).unwrap();
+
+            set_file_mtime(
+                manifest.path(),
+                FileTime::from_system_time(SystemTime::now() - Duration::from_secs(age)),
+            )
+            .unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn deletes_stale_hashes() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            create_hash(&dir, "old", 10, DAY * 10);
+            create_hash(&dir, "new", 10, DAY);
+
+            let (deleted, freed) = cache
+                .clean_stale_cache(Duration::from_secs(DAY * 7), None)
+                .await
+                .unwrap();
+
+            assert_eq!(deleted, 1);
+            assert_eq!(freed, 12);
+            assert!(!cache.hashes_dir.join("old.json").exists());
+            assert!(!cache.outputs_dir.join("old.tar.zst").exists());
+            assert!(cache.hashes_dir.join("new.json").exists());
+            assert!(cache.outputs_dir.join("new.tar.zst").exists());
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn deletes_least_recently_used_when_over_size() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            create_hash(&dir, "a", 98, 300);
+            create_hash(&dir, "b", 98, 200);
+            create_hash(&dir, "c", 98, 100);
+
+            let (deleted, _) = cache
+                .clean_stale_cache(Duration::from_secs(DAY), Some(250))
+                .await
+                .unwrap();
+
+            assert_eq!(deleted, 1);
+            assert!(!cache.hashes_dir.join("a.json").exists());
+            assert!(cache.hashes_dir.join("b.json").exists());
+            assert!(cache.hashes_dir.join("c.json").exists());
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn deletes_orphaned_outputs() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            create_hash(&dir, "abc", 10, 0);
+
+            dir.child(".moon/cache/out/orphan.tar.zst")
+                .write_str("1234")
+                .unwrap();
+            dir.child(".moon/cache/out/legacy/file.js")
+                .write_str("123456")
+                .unwrap();
+            dir.child(".moon/cache/out/legacy/nested/file.js")
+                .write_str("12")
+                .unwrap();
+
+            let (_, freed) = cache
+                .clean_stale_cache(Duration::from_secs(DAY), None)
+                .await
+                .unwrap();
+
+            assert_eq!(freed, 12);
+            assert!(cache.outputs_dir.join("abc.tar.zst").exists());
+            assert!(!cache.outputs_dir.join("orphan.tar.zst").exists());
+            assert!(!cache.outputs_dir.join("legacy").exists());
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn skips_hashes_locked_by_another_process() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            create_hash(&dir, "locked", 10, DAY * 10);
+            create_hash(&dir, "unlocked", 10, DAY * 10);
+
+            let lock = cache.lock_hash("locked").await.unwrap();
+
+            let (deleted, _) = cache
+                .clean_stale_cache(Duration::from_secs(DAY), None)
+                .await
+                .unwrap();
+
+            assert_eq!(deleted, 1);
+            assert!(cache.hashes_dir.join("locked.json").exists());
+            assert!(cache.outputs_dir.join("locked.tar.zst").exists());
+            assert!(!cache.hashes_dir.join("unlocked.json").exists());
+            assert!(!cache.hashes_dir.join("unlocked.lock").exists());
+
+            drop(lock);
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn deletes_orphaned_locks() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            create_hash(&dir, "abc", 10, 0);
+
+            dir.child(".moon/cache/hashes/abc.lock")
+                .write_str("")
+                .unwrap();
+            dir.child(".moon/cache/hashes/orphan.lock")
+                .write_str("")
+                .unwrap();
+
+            cache
+                .clean_stale_cache(Duration::from_secs(DAY), None)
+                .await
+                .unwrap();
+
+            assert!(cache.hashes_dir.join("abc.lock").exists());
+            assert!(!cache.hashes_dir.join("orphan.lock").exists());
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn doesnt_delete_if_cache_readonly() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            create_hash(&dir, "old", 10, DAY * 10);
+
+            let (deleted, _) = run_with_env("read", || {
+                cache.clean_stale_cache(Duration::from_secs(DAY), None)
+            })
+            .await
+            .unwrap();
+
+            assert_eq!(deleted, 0);
+            assert!(cache.hashes_dir.join("old.json").exists());
+
+            dir.close().unwrap();
+        }
+    }
+
+    mod lock_target {
+        use super::*;
+
+        #[tokio::test]
+        #[serial]
+        async fn creates_lock_in_run_dir() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            let lock = cache.lock_target("foo:bar").await.unwrap();
+
+            assert_eq!(
+                lock.path,
+                dir.path()
+                    .join(".moon/cache/runs/foo/bar/lastRunState.lock")
+            );
+            assert!(lock.path.exists());
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn locks_are_exclusive() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            let lock = cache.lock_target("foo:bar").await.unwrap();
+
+            assert!(CacheLock::try_acquire(lock.path.clone()).unwrap().is_none());
+
+            dir.close().unwrap();
+        }
+    }
+
+    mod touch_hash {
+        use super::*;
+
+        #[tokio::test]
+        #[serial]
+        async fn updates_modified_time() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+            let manifest = dir.child(".moon/cache/hashes/abc123.json");
+
+            manifest.write_str("{}").unwrap();
+
+            set_file_mtime(manifest.path(), FileTime::from_unix_time(0, 0)).unwrap();
+
+            cache.touch_hash("abc123").await.unwrap();
+
+            assert!(
+                fs::metadata(manifest.path()).unwrap().modified().unwrap() > SystemTime::UNIX_EPOCH
+            );
+
+            dir.close().unwrap();
+        }
+    }
+
+    mod remote {
+        use super::*;
+        use mockito::mock;
+        use moon_config::RemoteCacheConfig;
+
+        fn create_remote() -> Option<RemoteCache> {
+            Some(RemoteCache::new(&RemoteCacheConfig {
+                host: mockito::server_url(),
+            }))
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn doesnt_download_if_not_configured() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            assert!(!cache.download_from_remote("abc123").await.unwrap());
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn downloads_hash_and_outputs() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let mut cache = CacheEngine::create(dir.path()).await.unwrap();
+            cache.remote = create_remote();
+
+            let hash = mock("GET", "/hashes/abc123").with_body("{}").create();
+            let outputs = mock("GET", "/outputs/abc123").with_body("archive").create();
+
+            assert!(cache.download_from_remote("abc123").await.unwrap());
+
+            hash.assert();
+            outputs.assert();
+
+            assert_eq!(
+                fs::read_to_string(cache.hashes_dir.join("abc123.json")).unwrap(),
+                "{}"
+            );
+            assert_eq!(
+                fs::read_to_string(cache.outputs_dir.join("abc123.tar.zst")).unwrap(),
+                "archive"
+            );
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn returns_false_if_hash_missing() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let mut cache = CacheEngine::create(dir.path()).await.unwrap();
+            cache.remote = create_remote();
+
+            let hash = mock("GET", "/hashes/abc123").with_status(404).create();
+            let outputs = mock("GET", "/outputs/abc123").expect(0).create();
+
+            assert!(!cache.download_from_remote("abc123").await.unwrap());
+
+            hash.assert();
+            outputs.assert();
+
+            assert!(!cache.hashes_dir.join("abc123.json").exists());
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn errors_on_server_failure() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let mut cache = CacheEngine::create(dir.path()).await.unwrap();
+            cache.remote = create_remote();
+
+            let _hash = mock("GET", "/hashes/abc123").with_status(500).create();
+
+            assert!(cache.download_from_remote("abc123").await.is_err());
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn doesnt_download_if_cache_off() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let mut cache = CacheEngine::create(dir.path()).await.unwrap();
+            cache.remote = create_remote();
+
+            let hash = mock("GET", "/hashes/abc123").expect(0).create();
+
+            assert!(
+                !run_with_env("off", || cache.download_from_remote("abc123"))
+                    .await
+                    .unwrap()
+            );
+
+            hash.assert();
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn uploads_hash_and_outputs() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let mut cache = CacheEngine::create(dir.path()).await.unwrap();
+            cache.remote = create_remote();
+
+            dir.child(".moon/cache/hashes/abc123.json")
+                .write_str("{}")
+                .unwrap();
+
+            dir.child(".moon/cache/out/abc123.tar.zst")
+                .write_str("archive")
+                .unwrap();
+
+            let hash = mock("PUT", "/hashes/abc123").match_body("{}").create();
+            let outputs = mock("PUT", "/outputs/abc123")
+                .match_body("archive")
+                .create();
+
+            cache.upload_to_remote("abc123").await.unwrap();
+
+            hash.assert();
+            outputs.assert();
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn doesnt_upload_if_cache_readonly() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let mut cache = CacheEngine::create(dir.path()).await.unwrap();
+            cache.remote = create_remote();
+
+            dir.child(".moon/cache/hashes/abc123.json")
+                .write_str("{}")
+                .unwrap();
+
+            let hash = mock("PUT", "/hashes/abc123").expect(0).create();
+            let outputs = mock("PUT", "/outputs/abc123").expect(0).create();
+
+            run_with_env("read", || cache.upload_to_remote("abc123"))
+                .await
+                .unwrap();
+
+            hash.assert();
+            outputs.assert();
+
+            dir.close().unwrap();
+        }
+    }
+
+    mod archive_task_outputs {
+        use super::*;
+
+        #[tokio::test]
+        #[serial]
+        async fn creates_archive() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            dir.child("project/file.js").write_str("file").unwrap();
 
-    pub async fn delete_hash(&self, hash: &str) -> Result<(), MoonError> {
-        if is_writable() {
-            let path = self.hashes_dir.join(format!("{}.json", hash));
+            let project_root = dir.path().join("project");
 
-            trace!(target: "moon:cache:hash", "Deleting hash {}", color::path(&path));
+            cache
+                .archive_task_outputs("abc123", &project_root, &[project_root.join("file.js")])
+                .await
+                .unwrap();
 
-            // Remove the hash file itself
-            fs::remove_file(&path).await?;
+            assert!(cache.outputs_dir.join("abc123.tar.zst").exists());
 
-            // And the output with the hash
-            fs::remove_dir_all(&self.outputs_dir.join(hash)).await?;
+            dir.close().unwrap();
         }
 
-        Ok(())
-    }
+        #[tokio::test]
+        #[serial]
+        async fn doesnt_create_if_cache_readonly() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
 
-    pub async fn delete_runfiles(&self) -> Result<(), MoonError> {
-        let entries = fs::read_dir(&self.runs_dir).await?;
+            dir.child("project/file.js").write_str("file").unwrap();
 
-        for entry in entries {
-            let path = entry.path();
+            let project_root = dir.path().join("project");
 
-            if path.is_dir() {
-                let runfile = path.join("runfile.json");
+            run_with_env("read", || {
+                cache.archive_task_outputs("abc123", &project_root, &[project_root.join("file.js")])
+            })
+            .await
+            .unwrap();
 
-                trace!(target: "moon:cache:runfile", "Deleting runfile {}", color::path(&runfile));
+            assert!(!cache.outputs_dir.join("abc123.tar.zst").exists());
 
-                fs::remove_file(&runfile).await?;
-            }
+            dir.close().unwrap();
         }
-
-        Ok(())
     }
 
-    pub async fn link_task_output_to_out(
-        &self,
-        hash: &str,
-        source_root: &Path,
-        source_path: &Path,
-    ) -> Result<(), MoonError> {
-        if is_writable() {
-            let dest_root = self.outputs_dir.join(hash);
+    mod hydrate_from_out {
+        use super::*;
+
+        #[tokio::test]
+        #[serial]
+        async fn restores_files_and_dirs() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
 
-            fs::create_dir_all(&dest_root).await?;
+            dir.child("project/file.js").write_str("file").unwrap();
 
-            trace!(
-                target: LOG_TARGET,
-                "Hardlinking output {} to {}",
-                color::path(source_path),
-                color::path(&dest_root)
+            dir.child("project/lib/nested/index.js")
+                .write_str("index")
+                .unwrap();
+
+            let project_root = dir.path().join("project");
+            let outputs = vec![project_root.join("file.js"), project_root.join("lib")];
+
+            cache
+                .archive_task_outputs("abc123", &project_root, &outputs)
+                .await
+                .unwrap();
+
+            fs::remove_dir_all(&project_root).unwrap();
+
+            let hydrated = cache
+                .hydrate_from_out("abc123", &project_root, &outputs)
+                .await
+                .unwrap();
+
+            assert!(hydrated);
+            assert_eq!(
+                fs::read_to_string(project_root.join("file.js")).unwrap(),
+                "file"
+            );
+            assert_eq!(
+                fs::read_to_string(project_root.join("lib/nested/index.js")).unwrap(),
+                "index"
             );
 
-            if source_path.is_file() {
-                fs::link_file(source_root, source_path, &dest_root).await?;
-            } else {
-                fs::link_dir(source_root, source_path, &dest_root).await?;
-            }
+            dir.close().unwrap();
         }
 
-        Ok(())
-    }
+        #[tokio::test]
+        #[serial]
+        async fn overwrites_existing_outputs() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
 
-    pub async fn save_hash<T>(&self, hash: &str, hasher: &T) -> Result<(), MoonError>
-    where
-        T: ?Sized + Serialize,
-    {
-        if is_writable() {
-            let path = self.hashes_dir.join(format!("{}.json", hash));
+            dir.child("project/file.js").write_str("cached").unwrap();
 
-            trace!(target: "moon:cache:hash", "Writing hash {}", color::path(&path));
+            let project_root = dir.path().join("project");
+            let outputs = vec![project_root.join("file.js")];
 
-            fs::write_json(&path, &hasher, true).await?;
-        }
+            cache
+                .archive_task_outputs("abc123", &project_root, &outputs)
+                .await
+                .unwrap();
 
-        Ok(())
-    }
-}
+            dir.child("project/file.js").write_str("stale").unwrap();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::helpers::{run_with_env, to_millis};
-    use assert_fs::prelude::*;
-    use serial_test::serial;
-    use std::fs;
+            cache
+                .hydrate_from_out("abc123", &project_root, &outputs)
+                .await
+                .unwrap();
 
-    mod create {
-        use super::*;
+            assert_eq!(
+                fs::read_to_string(project_root.join("file.js")).unwrap(),
+                "cached"
+            );
+
+            dir.close().unwrap();
+        }
 
         #[tokio::test]
         #[serial]
-        async fn creates_dirs() {
+        async fn returns_false_if_archive_is_missing() {
             let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
 
-            CacheEngine::create(dir.path()).await.unwrap();
+            let project_root = dir.path().join("project");
+            let hydrated = cache
+                .hydrate_from_out("abc123", &project_root, &[project_root.join("file.js")])
+                .await
+                .unwrap();
 
-            assert!(dir.path().join(".moon/cache").exists());
-            assert!(dir.path().join(".moon/cache/hashes").exists());
-            assert!(dir.path().join(".moon/cache/runs").exists());
-            assert!(dir.path().join(".moon/cache/out").exists());
+            assert!(!hydrated);
 
             dir.close().unwrap();
         }
-    }
-
-    mod delete_runfiles {
-        use super::*;
 
         #[tokio::test]
         #[serial]
-        async fn deletes_dir() {
+        async fn returns_false_if_an_output_is_missing() {
             let dir = assert_fs::TempDir::new().unwrap();
             let cache = CacheEngine::create(dir.path()).await.unwrap();
 
-            let runfile1 = cache
-                .create_runfile("123", &"content".to_owned())
-                .await
-                .unwrap();
-            let runfile2 = cache
-                .create_runfile("456", &"content".to_owned())
+            dir.child("project/file.js").write_str("file").unwrap();
+
+            let project_root = dir.path().join("project");
+
+            cache
+                .archive_task_outputs("abc123", &project_root, &[project_root.join("file.js")])
                 .await
                 .unwrap();
 
-            assert!(runfile1.path.exists());
-            assert!(runfile2.path.exists());
+            fs::remove_dir_all(&project_root).unwrap();
 
-            cache.delete_runfiles().await.unwrap();
+            let hydrated = cache
+                .hydrate_from_out(
+                    "abc123",
+                    &project_root,
+                    &[project_root.join("file.js"), project_root.join("lib")],
+                )
+                .await
+                .unwrap();
 
-            assert!(!runfile1.path.exists());
-            assert!(!runfile2.path.exists());
+            assert!(!hydrated);
+            assert!(!project_root.join("file.js").exists());
 
             dir.close().unwrap();
         }
     }
 
-    mod delete_hash {
+    mod create_runfile {
         use super::*;
 
         #[tokio::test]
         #[serial]
-        async fn deletes_files() {
+        async fn creates_runfile_on_call() {
             let dir = assert_fs::TempDir::new().unwrap();
             let cache = CacheEngine::create(dir.path()).await.unwrap();
-
-            dir.child(".moon/cache/hashes/abc123.json")
-                .write_str("{}")
+            let runfile = cache
+                .create_runfile("123", &"content".to_owned())
+                .await
                 .unwrap();
 
-            dir.child(".moon/cache/out/abc123/file.js")
-                .write_str("")
-                .unwrap();
+            assert!(runfile.path.exists());
 
-            let hash_file = cache.hashes_dir.join("abc123.json");
-            let out_file = cache.outputs_dir.join("abc123/file.js");
+            assert_eq!(
+                fs::read_to_string(dir.path().join(".moon/cache/runs/123/runfile.json")).unwrap(),
+                "\"content\""
+            );
 
-            assert!(hash_file.exists());
-            assert!(out_file.exists());
+            dir.close().unwrap();
+        }
+    }
 
-            cache.delete_hash("abc123").await.unwrap();
+    mod write_run_log {
+        use super::*;
 
-            assert!(!hash_file.exists());
-            assert!(!out_file.exists());
+        #[tokio::test]
+        #[serial]
+        async fn writes_log_into_target_dir() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+            let path = cache.write_run_log("foo:bar", "content").await.unwrap();
+
+            assert!(path.starts_with(dir.path().join(".moon/cache/runs/foo/bar")));
+            assert_eq!(path.extension().unwrap(), "log");
+            assert_eq!(fs::read_to_string(&path).unwrap(), "content");
 
             dir.close().unwrap();
         }
 
         #[tokio::test]
         #[serial]
-        async fn doesnt_delete_if_cache_off() {
+        async fn writes_log_even_if_cache_off() {
             let dir = assert_fs::TempDir::new().unwrap();
             let cache = CacheEngine::create(dir.path()).await.unwrap();
-
-            dir.child(".moon/cache/hashes/abc123.json")
-                .write_str("{}")
+            let path = run_with_env("off", || cache.write_run_log("foo:bar", "content"))
+                .await
                 .unwrap();
 
-            dir.child(".moon/cache/out/abc123/file.js")
-                .write_str("")
-                .unwrap();
+            assert!(path.exists());
 
-            let hash_file = cache.hashes_dir.join("abc123.json");
-            let out_file = cache.outputs_dir.join("abc123/file.js");
+            dir.close().unwrap();
+        }
 
-            assert!(hash_file.exists());
-            assert!(out_file.exists());
+        #[tokio::test]
+        #[serial]
+        async fn deletes_oldest_logs_over_limit() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
 
-            run_with_env("off", || cache.delete_hash("abc123"))
-                .await
+            for i in 0..MAX_RUN_LOGS {
+                dir.child(format!(
+                    ".moon/cache/runs/foo/bar/2000-01-01T00-00-0{}.000Z.log",
+                    i
+                ))
+                .write_str("old")
                 .unwrap();
+            }
 
-            assert!(hash_file.exists());
-            assert!(out_file.exists());
+            let path = cache.write_run_log("foo:bar", "new").await.unwrap();
+            let logs = cache.get_run_logs("foo:bar").await.unwrap();
+
+            assert_eq!(logs.len(), MAX_RUN_LOGS);
+            assert_eq!(logs.last().unwrap(), &path);
+            assert!(!dir
+                .path()
+                .join(".moon/cache/runs/foo/bar/2000-01-01T00-00-00.000Z.log")
+                .exists());
 
             dir.close().unwrap();
         }
+    }
+
+    mod write_run_profile {
+        use super::*;
 
         #[tokio::test]
         #[serial]
-        async fn doesnt_delete_if_cache_readonly() {
+        async fn writes_profile_into_cache_dir() {
             let dir = assert_fs::TempDir::new().unwrap();
             let cache = CacheEngine::create(dir.path()).await.unwrap();
-
-            dir.child(".moon/cache/hashes/abc123.json")
-                .write_str("{}")
+            let path = cache
+                .write_run_profile(&"content".to_owned())
+                .await
                 .unwrap();
 
-            dir.child(".moon/cache/out/abc123/file.js")
-                .write_str("")
-                .unwrap();
+            assert_eq!(path, dir.path().join(".moon/cache/runProfile.json"));
+            assert_eq!(fs::read_to_string(&path).unwrap(), "\"content\"");
 
-            let hash_file = cache.hashes_dir.join("abc123.json");
-            let out_file = cache.outputs_dir.join("abc123/file.js");
+            dir.close().unwrap();
+        }
+    }
 
-            assert!(hash_file.exists());
-            assert!(out_file.exists());
+    mod write_run_report {
+        use super::*;
 
-            run_with_env("read", || cache.delete_hash("abc123"))
+        #[tokio::test]
+        #[serial]
+        async fn writes_report_even_if_cache_off() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+            let path = run_with_env("off", || cache.write_run_report(&"content".to_owned()))
                 .await
                 .unwrap();
 
-            assert!(hash_file.exists());
-            assert!(out_file.exists());
+            assert_eq!(path, dir.path().join(".moon/cache/runReport.json"));
+            assert_eq!(fs::read_to_string(&path).unwrap(), "\"content\"");
 
             dir.close().unwrap();
         }
     }
 
-    mod create_runfile {
+    mod get_last_run_log {
         use super::*;
 
         #[tokio::test]
         #[serial]
-        async fn creates_runfile_on_call() {
+        async fn returns_none_if_no_logs() {
             let dir = assert_fs::TempDir::new().unwrap();
             let cache = CacheEngine::create(dir.path()).await.unwrap();
-            let runfile = cache
-                .create_runfile("123", &"content".to_owned())
-                .await
-                .unwrap();
 
-            assert!(runfile.path.exists());
+            assert_eq!(cache.get_last_run_log("foo:bar").await.unwrap(), None);
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn returns_most_recent_log() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            dir.child(".moon/cache/runs/foo/bar/2022-01-01T00-00-00.000Z.log")
+                .write_str("first")
+                .unwrap();
+            dir.child(".moon/cache/runs/foo/bar/2022-02-01T00-00-00.000Z.log")
+                .write_str("second")
+                .unwrap();
+            dir.child(".moon/cache/runs/foo/bar/lastRunState.json")
+                .write_str("{}")
+                .unwrap();
 
             assert_eq!(
-                fs::read_to_string(dir.path().join(".moon/cache/runs/123/runfile.json")).unwrap(),
-                "\"content\""
+                cache.get_last_run_log("foo:bar").await.unwrap(),
+                Some(
+                    dir.path()
+                        .join(".moon/cache/runs/foo/bar/2022-02-01T00-00-00.000Z.log")
+                )
             );
 
             dir.close().unwrap();
@@ -727,6 +1898,44 @@ mod tests {
         }
     }
 
+    mod load_hash {
+        use super::*;
+        use std::collections::HashMap;
+
+        #[tokio::test]
+        #[serial]
+        async fn loads_existing_hash() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            dir.child(".moon/cache/hashes/abc123.json")
+                .write_str(r#"{"foo":"bar"}"#)
+                .unwrap();
+
+            let hash: Option<HashMap<String, String>> = cache.load_hash("abc123").await.unwrap();
+
+            assert_eq!(
+                hash,
+                Some(HashMap::from([("foo".to_owned(), "bar".to_owned())]))
+            );
+
+            dir.close().unwrap();
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn returns_none_if_missing() {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let cache = CacheEngine::create(dir.path()).await.unwrap();
+
+            let hash: Option<HashMap<String, String>> = cache.load_hash("abc123").await.unwrap();
+
+            assert_eq!(hash, None);
+
+            dir.close().unwrap();
+        }
+    }
+
     mod save_hash {
         use super::*;
         use serde::Deserialize;
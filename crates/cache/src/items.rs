@@ -69,7 +69,12 @@ impl<T: DeserializeOwned + Serialize> CacheItem<T> {
                 color::path(&self.path)
             );
 
-            fs::write_json(&self.path, &self.item, false).await?;
+            // Write to a temporary file first and then rename, so that
+            // other processes never read a partially written file
+            let temp_path = self.path.with_extension("tmp");
+
+            fs::write_json(&temp_path, &self.item, false).await?;
+            fs::rename(&temp_path, &self.path).await?;
         }
 
         Ok(())
@@ -1,8 +1,14 @@
+mod archive;
 mod engine;
 mod helpers;
 mod items;
+mod lock;
+mod remote;
 mod runfiles;
 
+pub use archive::{OutputManifest, OutputManifestFile};
 pub use engine::CacheEngine;
 pub use helpers::*;
 pub use items::*;
+pub use lock::CacheLock;
+pub use remote::RemoteCache;
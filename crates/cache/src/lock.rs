@@ -0,0 +1,205 @@
+use crate::helpers::LOG_TARGET;
+use fs2::{lock_contended_error, FileExt};
+use moon_error::{map_io_to_fs_error, MoonError};
+use moon_logger::{color, trace, warn};
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// An advisory lock on a file, that is shared across processes.
+/// The lock is released when dropped, or when the process exits.
+#[derive(Debug)]
+pub struct CacheLock {
+    file: File,
+
+    pub path: PathBuf,
+}
+
+fn open_lock_file(path: &Path) -> Result<File, MoonError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| map_io_to_fs_error(e, parent.to_path_buf()))?;
+    }
+
+    OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| map_io_to_fs_error(e, path.to_path_buf()))
+}
+
+fn is_contended(error: &io::Error) -> bool {
+    error.raw_os_error() == lock_contended_error().raw_os_error()
+}
+
+// The lock file may have been removed (by `moon clean`) while we were waiting on it,
+// in which case we hold a lock on a file that no other process can see, and another
+// process may have already created (and locked) a new file at the same path.
+#[cfg(unix)]
+fn is_locked_path(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(locked), Ok(current)) => locked.dev() == current.dev() && locked.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+// Open files can't be removed on Windows, so the locked file is always the current one.
+#[cfg(windows)]
+fn is_locked_path(_file: &File, _path: &Path) -> bool {
+    true
+}
+
+impl CacheLock {
+    /// Acquire an exclusive lock, waiting for another process to release it if necessary.
+    pub async fn acquire(path: PathBuf) -> Result<CacheLock, MoonError> {
+        loop {
+            let file = open_lock_file(&path)?;
+
+            let file = match file.try_lock_exclusive() {
+                Ok(_) => file,
+                Err(error) if is_contended(&error) => {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Waiting for another moon process to release the lock on {}",
+                        color::path(&path)
+                    );
+
+                    tokio::task::spawn_blocking(move || file.lock_exclusive().map(|_| file))
+                        .await
+                        .map_err(|e| MoonError::Unknown(io::Error::new(io::ErrorKind::Other, e)))?
+                        .map_err(|e| map_io_to_fs_error(e, path.clone()))?
+                }
+                Err(error) => return Err(map_io_to_fs_error(error, path)),
+            };
+
+            if is_locked_path(&file, &path) {
+                trace!(target: LOG_TARGET, "Acquired lock {}", color::path(&path));
+
+                return Ok(CacheLock { file, path });
+            }
+
+            trace!(
+                target: LOG_TARGET,
+                "Lock {} was removed while waiting for it, retrying",
+                color::path(&path)
+            );
+        }
+    }
+
+    /// Attempt to acquire an exclusive lock without waiting.
+    /// Returns `None` if another process is holding the lock.
+    pub fn try_acquire(path: PathBuf) -> Result<Option<CacheLock>, MoonError> {
+        loop {
+            let file = open_lock_file(&path)?;
+
+            match file.try_lock_exclusive() {
+                Ok(_) => {
+                    if is_locked_path(&file, &path) {
+                        return Ok(Some(CacheLock { file, path }));
+                    }
+                }
+                Err(error) if is_contended(&error) => return Ok(None),
+                Err(error) => return Err(map_io_to_fs_error(error, path)),
+            };
+        }
+    }
+}
+
+impl Drop for CacheLock {
+    fn drop(&mut self) {
+        // Closing the file will also release the lock,
+        // but be explicit in case the handle is leaked
+        let _ = self.file.unlock();
+
+        trace!(
+            target: LOG_TARGET,
+            "Released lock {}",
+            color::path(&self.path)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn creates_lock_file() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("locks/test.lock");
+
+        let lock = CacheLock::acquire(path.clone()).await.unwrap();
+
+        assert!(path.exists());
+        assert_eq!(lock.path, path);
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn cannot_acquire_while_locked() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("test.lock");
+
+        let lock = CacheLock::acquire(path.clone()).await.unwrap();
+
+        assert!(CacheLock::try_acquire(path.clone()).unwrap().is_none());
+
+        drop(lock);
+
+        assert!(CacheLock::try_acquire(path).unwrap().is_some());
+
+        dir.close().unwrap();
+    }
+
+    #[tokio::test]
+    async fn waits_for_lock_to_be_released() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("test.lock");
+
+        let lock = CacheLock::acquire(path.clone()).await.unwrap();
+
+        let handle = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(lock);
+        });
+
+        let waiting = CacheLock::acquire(path).await.unwrap();
+
+        handle.await.unwrap();
+        drop(waiting);
+
+        dir.close().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn retries_when_lock_file_is_removed() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("test.lock");
+
+        let lock = CacheLock::acquire(path.clone()).await.unwrap();
+        let waiting_path = path.clone();
+
+        let handle = tokio::spawn(async move { CacheLock::acquire(waiting_path).await.unwrap() });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Removed while held, like `moon clean` does
+        fs::remove_file(&path).unwrap();
+        drop(lock);
+
+        let waiting = handle.await.unwrap();
+
+        // The waiter must hold the lock on the file that's now at the path
+        assert!(path.exists());
+        assert!(CacheLock::try_acquire(path).unwrap().is_none());
+
+        drop(waiting);
+
+        dir.close().unwrap();
+    }
+}
@@ -0,0 +1,110 @@
+use crate::helpers::LOG_TARGET;
+use moon_config::RemoteCacheConfig;
+use moon_error::MoonError;
+use moon_logger::{color, trace};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use std::env;
+use std::time::Duration;
+
+/// How long to wait for a connection to the remote cache to be established.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a single request (including transferring its body) may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A remote cache that speaks a minimal HTTP protocol, relative to the configured host:
+///
+///   GET|PUT /hashes/<hash>   - The hash manifest (JSON of all hashed sources).
+///   GET|PUT /outputs/<hash>  - The task's outputs archive (`.tar.zst` with a manifest).
+///
+/// A GET that responds with a 404 is considered a cache miss. If the
+/// `MOON_REMOTE_CACHE_TOKEN` environment variable is set, it will be sent
+/// as a bearer token with every request. Requests that time out fail like
+/// any other request, and are treated as a cache miss by the caller.
+pub struct RemoteCache {
+    client: Client,
+
+    pub host: String,
+
+    token: Option<String>,
+}
+
+impl RemoteCache {
+    pub fn new(config: &RemoteCacheConfig) -> Self {
+        // An unresponsive server should never hang the run
+        let client = Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        RemoteCache {
+            client,
+            host: config.host.trim_end_matches('/').to_owned(),
+            token: env::var("MOON_REMOTE_CACHE_TOKEN").ok(),
+        }
+    }
+
+    pub async fn download_hash(&self, hash: &str) -> Result<Option<Vec<u8>>, MoonError> {
+        self.get(&self.create_url("hashes", hash)).await
+    }
+
+    pub async fn download_outputs(&self, hash: &str) -> Result<Option<Vec<u8>>, MoonError> {
+        self.get(&self.create_url("outputs", hash)).await
+    }
+
+    pub async fn upload_hash(&self, hash: &str, body: Vec<u8>) -> Result<(), MoonError> {
+        self.put(&self.create_url("hashes", hash), body).await
+    }
+
+    pub async fn upload_outputs(&self, hash: &str, body: Vec<u8>) -> Result<(), MoonError> {
+        self.put(&self.create_url("outputs", hash), body).await
+    }
+
+    fn create_url(&self, kind: &str, hash: &str) -> String {
+        format!("{}/{}/{}", self.host, kind, hash)
+    }
+
+    fn with_auth(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn get(&self, url: &str) -> Result<Option<Vec<u8>>, MoonError> {
+        trace!(target: LOG_TARGET, "Downloading {}", color::url(url));
+
+        let handle_error = |e: reqwest::Error| MoonError::Http(url.to_owned(), e.to_string());
+        let response = self
+            .with_auth(self.client.get(url))
+            .send()
+            .await
+            .map_err(handle_error)?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let response = response.error_for_status().map_err(handle_error)?;
+        let bytes = response.bytes().await.map_err(handle_error)?;
+
+        Ok(Some(bytes.to_vec()))
+    }
+
+    async fn put(&self, url: &str, body: Vec<u8>) -> Result<(), MoonError> {
+        trace!(target: LOG_TARGET, "Uploading {}", color::url(url));
+
+        let handle_error = |e: reqwest::Error| MoonError::Http(url.to_owned(), e.to_string());
+
+        self.with_auth(self.client.put(url))
+            .body(body)
+            .send()
+            .await
+            .map_err(handle_error)?
+            .error_for_status()
+            .map_err(handle_error)?;
+
+        Ok(())
+    }
+}
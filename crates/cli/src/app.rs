@@ -3,16 +3,34 @@
 use crate::commands::bin::BinTools;
 use crate::commands::init::{InheritProjectsAs, PackageManager};
 use crate::commands::run::RunStatus;
-use crate::enums::{CacheMode, LogLevel};
+use crate::enums::{CacheMode, LogLevel, OutputStyle, ProjectLanguage, ProjectType};
 use clap::{Parser, Subcommand};
 use moon_project::TargetID;
 use moon_terminal::output::label_moon;
+use std::path::PathBuf;
 
 pub const BIN_NAME: &str = if cfg!(windows) { "moon.exe" } else { "moon" };
 
 const HEADING_AFFECTED: &str = "Affected by changes";
+const HEADING_FILTERS: &str = "Filter projects of :task targets";
 const HEADING_PARALLELISM: &str = "Parallelism and distribution";
 
+#[derive(Debug, Subcommand)]
+pub enum HashCommands {
+    // moon hash diff <left> <right>
+    #[clap(
+        name = "diff",
+        about = "Compare the manifests of two hashes and list the differences."
+    )]
+    Diff {
+        #[clap(help = "Base hash to compare against")]
+        left: String,
+
+        #[clap(help = "Other hash to compare with")]
+        right: String,
+    },
+}
+
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     // ENVIRONMENT
@@ -75,6 +93,37 @@ pub enum Commands {
     )]
     Teardown,
 
+    // moon clean
+    #[clap(
+        name = "clean",
+        about = "Clean the workspace and delete stale hashes and outputs from the cache.",
+        rename_all = "camelCase"
+    )]
+    Clean {
+        #[clap(long, help = "Max age of unused hashes, in days")]
+        max_age: Option<u32>,
+
+        #[clap(long, help = "Max total size of hashes and outputs, in megabytes")]
+        max_size: Option<u32>,
+    },
+
+    // moon hash <command>
+    #[clap(name = "hash", about = "Inspect the hashes generated for targets.")]
+    Hash {
+        #[clap(subcommand)]
+        command: HashCommands,
+    },
+
+    // moon log <target>
+    #[clap(
+        name = "log",
+        about = "Print the log of a target's last run, including the output of every attempt."
+    )]
+    Log {
+        #[clap(help = "Target to print the log for")]
+        target: String,
+    },
+
     // PROJECTS
 
     // moon project <id>
@@ -101,6 +150,50 @@ pub enum Commands {
         id: Option<String>,
     },
 
+    // moon dep-graph [...targets]
+    #[clap(
+        name = "dep-graph",
+        about = "Display the graph of actions that would be ran for targets, in DOT or JSON format.",
+        rename_all = "camelCase"
+    )]
+    DepGraph {
+        #[clap(help = "List of targets (project:task) to graph", required = true)]
+        targets: Vec<TargetID>,
+
+        #[clap(
+            long,
+            help = "Include dependents of the same task, as well as dependencies"
+        )]
+        dependents: bool,
+
+        #[clap(long, help = "Print in JSON format")]
+        json: bool,
+
+        // Filters
+        #[clap(
+            arg_enum,
+            long,
+            help = "Only graph projects of this language",
+            help_heading = HEADING_FILTERS
+        )]
+        language: Vec<ProjectLanguage>,
+
+        #[clap(
+            long = "tag",
+            help = "Only graph projects with this tag",
+            help_heading = HEADING_FILTERS
+        )]
+        tags: Vec<String>,
+
+        #[clap(
+            arg_enum,
+            long = "type",
+            help = "Only graph projects of this type",
+            help_heading = HEADING_FILTERS
+        )]
+        type_of: Vec<ProjectType>,
+    },
+
     // JOBS
 
     // moon ci
@@ -116,21 +209,59 @@ pub enum Commands {
         #[clap(long, help = "Current branch, commit, or revision to compare with")]
         head: Option<String>,
 
+        #[clap(
+            long,
+            alias = "dry-run",
+            help = "Print the actions that would be ran, without running them"
+        )]
+        dry_run: bool,
+
+        #[clap(long, help = "Print the dry run in JSON format")]
+        json: bool,
+
+        #[clap(
+            long,
+            help = "Max number of actions to run in parallel",
+            help_heading = HEADING_PARALLELISM
+        )]
+        concurrency: Option<usize>,
+
         #[clap(long, help = "Index of the current job", help_heading = HEADING_PARALLELISM)]
         job: Option<usize>,
 
         #[clap(long, help = "Total amount of jobs to run", help_heading = HEADING_PARALLELISM)]
         job_total: Option<usize>,
+
+        #[clap(
+            arg_enum,
+            long,
+            alias = "output-style",
+            help = "How to print the output of tasks (overrides the task option)"
+        )]
+        output_style: Option<OutputStyle>,
+
+        #[clap(
+            long,
+            help = "Record a timeline of all actions in the Chrome trace format"
+        )]
+        profile: bool,
+
+        #[clap(long, help = "Write a JSON report of all actions to this path")]
+        report: Option<PathBuf>,
     },
 
     // moon run [...targets]
     #[clap(
         name = "run",
-        about = "Run a project task and all its dependent tasks."
+        about = "Run one or many project tasks and all their dependent tasks.",
+        rename_all = "camelCase"
     )]
     Run {
-        #[clap(help = "Target (project:task) to run")]
-        target: TargetID,
+        #[clap(help = "List of targets (project:task) to run", required = true)]
+        targets: Vec<TargetID>,
+
+        #[clap(long, help = "Max number of actions to run in parallel")]
+        concurrency: Option<usize>,
 
         #[clap(
             long,
@@ -138,6 +269,73 @@ pub enum Commands {
         )]
         dependents: bool,
 
+        #[clap(
+            long,
+            alias = "dry-run",
+            help = "Print the actions that would be ran, without running them"
+        )]
+        dry_run: bool,
+
+        #[clap(long, help = "Explain why a target's hash changed since its last run")]
+        explain: bool,
+
+        #[clap(long, help = "Print the dry run in JSON format")]
+        json: bool,
+
+        #[clap(
+            long,
+            help = "Continue running independent tasks when a task fails, skipping its dependents"
+        )]
+        no_bail: bool,
+
+        #[clap(
+            arg_enum,
+            long,
+            alias = "output-style",
+            help = "How to print the output of tasks (overrides the task option)"
+        )]
+        output_style: Option<OutputStyle>,
+
+        #[clap(
+            long,
+            help = "Record a timeline of all actions in the Chrome trace format"
+        )]
+        profile: bool,
+
+        #[clap(long, help = "Write a JSON report of all actions to this path")]
+        report: Option<PathBuf>,
+
+        #[clap(
+            long,
+            conflicts_with = "dryRun",
+            help = "Re-run affected targets when files change, until interrupted"
+        )]
+        watch: bool,
+
+        // Filters
+        #[clap(
+            arg_enum,
+            long,
+            help = "Only run in projects of this language",
+            help_heading = HEADING_FILTERS
+        )]
+        language: Vec<ProjectLanguage>,
+
+        #[clap(
+            long = "tag",
+            help = "Only run in projects with this tag",
+            help_heading = HEADING_FILTERS
+        )]
+        tags: Vec<String>,
+
+        #[clap(
+            arg_enum,
+            long = "type",
+            help = "Only run in projects of this type",
+            help_heading = HEADING_FILTERS
+        )]
+        type_of: Vec<ProjectType>,
+
         // Affected
         #[clap(
             long,
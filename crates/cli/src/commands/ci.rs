@@ -1,36 +1,49 @@
-use crate::commands::run::render_result_stats;
+use crate::commands::run::{render_result_stats, render_run_plan};
+use crate::enums::OutputStyle;
 use console::Term;
 use itertools::Itertools;
+use moon_config::TaskOutputStyle;
 use moon_logger::{color, debug};
 use moon_project::{Target, TouchedFilePaths};
 use moon_terminal::helpers::{replace_style_tokens, safe_exit};
 use moon_terminal::output;
 use moon_utils::{is_ci, path, time};
 use moon_workspace::DepGraph;
-use moon_workspace::{ActionRunner, ActionStatus, Workspace, WorkspaceError};
+use moon_workspace::{ActionRunner, ActionStatus, RunPlan, Workspace, WorkspaceError};
 use std::collections::HashSet;
+use std::env;
 use std::path::PathBuf;
 
 type TargetList = Vec<Target>;
 
 const TARGET: &str = "moon:ci";
 
-fn print_header(title: &str) {
+// When printing a dry run as JSON, progress is written to stderr,
+// so that stdout can be piped to other tools.
+fn print_line(options: &CiOptions, line: &str) {
+    if options.dry_run && options.json {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+fn print_header(options: &CiOptions, title: &str) {
     let prefix = if is_ci() { "--- " } else { "" };
 
-    println!("{}{}", prefix, title);
+    print_line(options, &format!("{}{}", prefix, title));
 }
 
-fn print_targets(targets: &TargetList) {
+fn print_targets(options: &CiOptions, targets: &TargetList) {
     let mut targets_to_print = targets.clone();
     targets_to_print.sort();
 
-    println!(
-        "{}",
-        targets_to_print
+    print_line(
+        options,
+        &targets_to_print
             .iter()
             .map(|t| format!("  {}", color::target(&t.id)))
-            .join("\n")
+            .join("\n"),
     );
 }
 
@@ -39,7 +52,7 @@ async fn gather_touched_files(
     workspace: &Workspace,
     options: &CiOptions,
 ) -> Result<TouchedFilePaths, WorkspaceError> {
-    print_header("Gathering touched files");
+    print_header(options, "Gathering touched files");
 
     let vcs = workspace.detect_vcs()?;
     let default_branch = vcs.get_default_branch();
@@ -74,7 +87,7 @@ async fn gather_touched_files(
 
     touched_files_to_print.sort();
 
-    println!("{}", touched_files_to_print.join("\n"));
+    print_line(options, &touched_files_to_print.join("\n"));
 
     Ok(touched_files)
 }
@@ -82,9 +95,10 @@ async fn gather_touched_files(
 /// Gather runnable targets by checking if all projects/tasks are affected based on touched files.
 fn gather_runnable_targets(
     workspace: &Workspace,
+    options: &CiOptions,
     touched_files: &TouchedFilePaths,
 ) -> Result<TargetList, WorkspaceError> {
-    print_header("Gathering runnable targets");
+    print_header(options, "Gathering runnable targets");
 
     let mut targets = vec![];
     let globally_affected = workspace.projects.is_globally_affected(touched_files);
@@ -117,12 +131,12 @@ fn gather_runnable_targets(
     }
 
     if targets.is_empty() {
-        println!(
-            "{}",
-            color::invalid("No targets to run based on touched files")
+        print_line(
+            options,
+            &color::invalid("No targets to run based on touched files"),
         );
     } else {
-        print_targets(&targets);
+        print_targets(options, &targets);
     }
 
     Ok(targets)
@@ -139,11 +153,11 @@ fn distribute_targets_across_jobs(options: &CiOptions, targets: TargetList) -> T
     let batch_size = targets.len() / job_total;
     let batched_targets;
 
-    print_header("Distributing targets across jobs");
-    println!("Job index: {}", job_index);
-    println!("Job total: {}", job_index);
-    println!("Batch size: {}", batch_size);
-    println!("Batched targets:");
+    print_header(options, "Distributing targets across jobs");
+    print_line(options, &format!("Job index: {}", job_index));
+    print_line(options, &format!("Job total: {}", job_index));
+    print_line(options, &format!("Batch size: {}", batch_size));
+    print_line(options, "Batched targets:");
 
     if job_index == 0 {
         batched_targets = targets[0..batch_size].to_vec();
@@ -154,7 +168,7 @@ fn distribute_targets_across_jobs(options: &CiOptions, targets: TargetList) -> T
             targets[(batch_size * job_index)..(batch_size * (job_index + 1))].to_vec();
     }
 
-    print_targets(&batched_targets);
+    print_targets(options, &batched_targets);
 
     batched_targets
 }
@@ -162,9 +176,10 @@ fn distribute_targets_across_jobs(options: &CiOptions, targets: TargetList) -> T
 /// Generate a dependency graph with the runnable targets.
 fn generate_dep_graph(
     workspace: &Workspace,
+    options: &CiOptions,
     targets: &TargetList,
 ) -> Result<DepGraph, WorkspaceError> {
-    print_header("Generating dependency graph");
+    print_header(options, "Generating dependency graph");
 
     let mut dep_graph = DepGraph::default();
 
@@ -176,8 +191,11 @@ fn generate_dep_graph(
         dep_graph.run_target_dependents(target, &workspace.projects)?;
     }
 
-    println!("Target count: {}", targets.len());
-    println!("Action count: {}", dep_graph.graph.node_count());
+    print_line(options, &format!("Target count: {}", targets.len()));
+    print_line(
+        options,
+        &format!("Action count: {}", dep_graph.graph.node_count()),
+    );
 
     Ok(dep_graph)
 }
@@ -185,40 +203,72 @@ fn generate_dep_graph(
 pub struct CiOptions {
     pub base: Option<String>,
     pub head: Option<String>,
+    pub dry_run: bool,
+    pub json: bool,
+    pub concurrency: Option<usize>,
     pub job: Option<usize>,
     pub job_total: Option<usize>,
+    pub output_style: Option<OutputStyle>,
+    pub profile: bool,
+    pub report: Option<PathBuf>,
 }
 
 pub async fn ci(options: CiOptions) -> Result<(), Box<dyn std::error::Error>> {
     let workspace = Workspace::load().await?;
     let touched_files = gather_touched_files(&workspace, &options).await?;
-    let targets = gather_runnable_targets(&workspace, &touched_files)?;
+    let targets = gather_runnable_targets(&workspace, &options, &touched_files)?;
 
     if targets.is_empty() {
+        if options.dry_run && options.json {
+            println!("{}", RunPlan::default().to_json());
+        }
+
         return Ok(());
     }
 
     let targets = distribute_targets_across_jobs(&options, targets);
-    let dep_graph = generate_dep_graph(&workspace, &targets)?;
+    let dep_graph = generate_dep_graph(&workspace, &options, &targets)?;
+
+    let mut runner = ActionRunner::new(workspace);
+
+    if let Some(concurrency) = options.concurrency {
+        runner.set_concurrency(concurrency);
+    }
+
+    runner.set_output_style(options.output_style.as_ref().map(TaskOutputStyle::from));
+    runner.set_profile(options.profile);
+
+    if let Some(report) = &options.report {
+        runner.set_report_path(env::current_dir()?.join(report));
+    }
+
+    if options.dry_run {
+        print_header(&options, "Planning all targets");
+        render_run_plan(&runner.plan(&dep_graph).await?, options.json)?;
+
+        return Ok(());
+    }
 
     // Process all tasks in the graph
-    print_header("Running all targets");
+    print_header(&options, "Running all targets");
 
-    let mut runner = ActionRunner::new(workspace);
     let results = runner.run(dep_graph).await?;
 
     // Print out the results and exit if an error occurs
     let mut error_count = 0;
     let term = Term::buffered_stdout();
 
-    print_header("Results");
+    print_header(&options, "Results");
 
     for result in &results {
         let status = match result.status {
-            ActionStatus::Passed | ActionStatus::Cached | ActionStatus::Skipped => {
-                color::success("pass")
-            }
+            ActionStatus::Passed
+            | ActionStatus::Cached
+            | ActionStatus::CachedFromRemote
+            | ActionStatus::Skipped => color::success("pass"),
             ActionStatus::Failed | ActionStatus::FailedAndAbort => color::failure("fail"),
+            ActionStatus::SkippedFromFailure => color::muted_light("skip"),
+            ActionStatus::Interrupted => color::invalid("stop"),
             ActionStatus::Invalid => color::invalid("warn"),
             _ => color::muted_light("oops"),
         };
@@ -227,7 +277,12 @@ pub async fn ci(options: CiOptions) -> Result<(), Box<dyn std::error::Error>> {
 
         if matches!(result.status, ActionStatus::Cached) {
             meta.push(String::from("cached"));
-        } else if matches!(result.status, ActionStatus::Skipped) {
+        } else if matches!(result.status, ActionStatus::CachedFromRemote) {
+            meta.push(String::from("cached from remote"));
+        } else if matches!(
+            result.status,
+            ActionStatus::Skipped | ActionStatus::SkippedFromFailure
+        ) {
             meta.push(String::from("skipped"));
         } else {
             meta.push(time::elapsed(result.duration.unwrap()));
@@ -253,6 +308,10 @@ pub async fn ci(options: CiOptions) -> Result<(), Box<dyn std::error::Error>> {
 
     render_result_stats(results, runner.duration.unwrap(), true)?;
 
+    if runner.was_interrupted() {
+        safe_exit(130);
+    }
+
     if error_count > 0 {
         safe_exit(1);
     }
@@ -0,0 +1,46 @@
+use moon_logger::color;
+use moon_workspace::Workspace;
+use std::time::Duration;
+
+pub struct CleanOptions {
+    pub max_age: Option<u32>,
+    pub max_size: Option<u32>,
+}
+
+fn format_bytes(bytes: u64) -> String {
+    let kb = bytes as f64 / 1024.0;
+    let mb = kb / 1024.0;
+
+    if mb >= 1.0 {
+        format!("{:.2} MB", mb)
+    } else if kb >= 1.0 {
+        format!("{:.2} KB", kb)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+pub async fn clean(options: CleanOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = Workspace::load().await?;
+    let config = &workspace.config.cache;
+
+    // Options passed on the command line take precedence over the config
+    let max_age = options.max_age.unwrap_or(config.max_age);
+    let max_size = options.max_size.or(config.max_size);
+
+    let (deleted_count, freed_bytes) = workspace
+        .cache
+        .clean_stale_cache(
+            Duration::from_secs(max_age as u64 * 86400),
+            max_size.map(|size| size as u64 * 1024 * 1024),
+        )
+        .await?;
+
+    println!(
+        "Cleaned {} stale hashes and freed {}",
+        color::symbol(&deleted_count.to_string()),
+        color::symbol(&format_bytes(freed_bytes))
+    );
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use crate::commands::run::{generate_dep_graph, RunOptions};
+use crate::enums::{ProjectLanguage, ProjectType};
+use moon_project::Target;
+use moon_workspace::Workspace;
+
+pub struct DepGraphOptions {
+    pub dependents: bool,
+    pub json: bool,
+    pub language: Vec<ProjectLanguage>,
+    pub tags: Vec<String>,
+    pub type_of: Vec<ProjectType>,
+}
+
+pub async fn dep_graph(
+    target_ids: &[String],
+    options: DepGraphOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let targets = target_ids
+        .iter()
+        .map(|target_id| Target::parse(target_id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let workspace = Workspace::load().await?;
+
+    // Build the same graph that `moon run` would, but don't report
+    // missing targets, as it would corrupt the output
+    let (dep_graph, _) = generate_dep_graph(
+        &workspace,
+        &RunOptions {
+            dependents: options.dependents,
+            language: options.language,
+            tags: options.tags,
+            type_of: options.type_of,
+            ..RunOptions::default()
+        },
+        &targets,
+        None,
+        false,
+    )?;
+
+    if options.json {
+        println!("{}", dep_graph.to_json()?);
+    } else {
+        println!("{}", dep_graph.to_dot());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use moon_hasher::TargetHasher;
+use moon_logger::color;
+use moon_workspace::{Workspace, WorkspaceError};
+
+async fn load_hasher(workspace: &Workspace, hash: &str) -> Result<TargetHasher, WorkspaceError> {
+    match workspace.cache.load_hash::<TargetHasher>(hash).await? {
+        Some(hasher) => Ok(hasher),
+        None => Err(WorkspaceError::MissingHashManifest(hash.to_owned())),
+    }
+}
+
+pub async fn hash_diff(left: &str, right: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = Workspace::load().await?;
+    let left_hasher = load_hasher(&workspace, left).await?;
+    let right_hasher = load_hasher(&workspace, right).await?;
+    let diffs = right_hasher.diff(&left_hasher);
+
+    if diffs.is_empty() {
+        println!(
+            "No differences between {} and {}",
+            color::symbol(left),
+            color::symbol(right)
+        );
+
+        return Ok(());
+    }
+
+    println!(
+        "Differences between {} and {}",
+        color::symbol(left),
+        color::symbol(right)
+    );
+
+    for diff in diffs {
+        println!("  {}", diff);
+    }
+
+    Ok(())
+}
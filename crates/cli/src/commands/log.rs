@@ -0,0 +1,22 @@
+use moon_project::Target;
+use moon_utils::fs;
+use moon_workspace::{Workspace, WorkspaceError};
+
+pub async fn log(target_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // Logs are grouped by project and task, so scopes are not supported
+    let target = Target::parse(target_id)?;
+    target.ids()?;
+
+    let workspace = Workspace::load().await?;
+
+    let path = match workspace.cache.get_last_run_log(&target.id).await? {
+        Some(path) => path,
+        None => return Err(WorkspaceError::MissingRunLog(target.id).into()),
+    };
+
+    let contents = fs::read(&path).await?;
+
+    print!("{}", String::from_utf8_lossy(&contents));
+
+    Ok(())
+}
@@ -1,6 +1,10 @@
 pub mod bin;
 pub mod ci;
+pub mod clean;
+pub mod dep_graph;
+pub mod hash;
 pub mod init;
+pub mod log;
 pub mod project;
 pub mod project_graph;
 pub mod run;
@@ -31,6 +31,10 @@ pub async fn project(id: &str, json: bool) -> Result<(), Box<dyn std::error::Err
         term.render_entry("Language", &term.format(&config.language))?;
         term.render_entry("Type", &term.format(&config.type_of))?;
 
+        if !config.tags.is_empty() {
+            term.render_entry_list("Tags", &config.tags)?;
+        }
+
         if let Some(meta) = config.project {
             term.render_entry("Name", &meta.name)?;
             term.render_entry("Description", &meta.description)?;
@@ -1,15 +1,33 @@
+use crate::enums::{OutputStyle, ProjectLanguage, ProjectType};
 use clap::ArgEnum;
 use console::Term;
+use moon_config::TaskOutputStyle;
 use moon_logger::color;
-use moon_project::{Target, TouchedFilePaths};
-use moon_terminal::ExtendedTerm;
+use moon_project::{ProjectFilter, Target, TouchedFilePaths};
+use moon_terminal::helpers::safe_exit;
+use moon_terminal::{output, ExtendedTerm};
+use moon_utils::process::ProcessSignal;
 use moon_utils::time;
-use moon_workspace::{Action, ActionRunner, ActionStatus, DepGraph, Workspace, WorkspaceError};
+use moon_workspace::{
+    Action, ActionRunner, ActionStatus, DepGraph, FileWatcher, Node, RunPlan, Workspace,
+    WorkspaceError,
+};
 use std::collections::HashSet;
+use std::env;
+use std::path::PathBuf;
 use std::string::ToString;
 use std::time::Duration;
 use strum_macros::Display;
 
+/// How long to wait for more files to change, before re-running targets.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(100);
+
+// Windows processes can't be gracefully terminated, so kill them instead
+#[cfg(unix)]
+const RESTART_SIGNAL: ProcessSignal = ProcessSignal::Terminate;
+#[cfg(windows)]
+const RESTART_SIGNAL: ProcessSignal = ProcessSignal::Kill;
+
 #[derive(ArgEnum, Clone, Debug, Display)]
 pub enum RunStatus {
     Added,
@@ -27,12 +45,25 @@ impl Default for RunStatus {
     }
 }
 
+#[derive(Default)]
 pub struct RunOptions {
     pub affected: bool,
+    pub concurrency: Option<usize>,
     pub dependents: bool,
+    pub dry_run: bool,
+    pub explain: bool,
+    pub json: bool,
+    pub language: Vec<ProjectLanguage>,
+    pub no_bail: bool,
+    pub output_style: Option<OutputStyle>,
+    pub profile: bool,
+    pub report: Option<PathBuf>,
+    pub tags: Vec<String>,
+    pub type_of: Vec<ProjectType>,
     pub status: RunStatus,
     pub passthrough: Vec<String>,
     pub upstream: bool,
+    pub watch: bool,
 }
 
 async fn get_touched_files(
@@ -68,6 +99,107 @@ async fn get_touched_files(
     Ok(touched)
 }
 
+/// List actions that failed, and their dependents that were skipped because of it.
+pub fn render_failures(results: &[Action]) -> Result<(), Box<dyn std::error::Error>> {
+    let failed = results
+        .iter()
+        .filter(|result| result.has_failed())
+        .collect::<Vec<_>>();
+    let skipped = results
+        .iter()
+        .filter(|result| result.was_skipped_from_failure())
+        .collect::<Vec<_>>();
+
+    if failed.is_empty() && skipped.is_empty() {
+        return Ok(());
+    }
+
+    let term = Term::buffered_stdout();
+    term.write_line("")?;
+
+    for result in failed {
+        term.write_line(&format!(
+            "{} {}",
+            color::failure("fail"),
+            output::bold(result.label.as_ref().unwrap())
+        ))?;
+    }
+
+    for result in skipped {
+        term.write_line(&format!(
+            "{} {} {}",
+            color::muted_light("skip"),
+            output::bold(result.label.as_ref().unwrap()),
+            color::muted(&format!("({})", result.error.as_ref().unwrap()))
+        ))?;
+    }
+
+    term.flush()?;
+
+    Ok(())
+}
+
+/// Print the actions that would be ran, and the commands of targets, for dry runs.
+pub fn render_run_plan(plan: &RunPlan, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if json {
+        println!("{}", plan.to_json());
+
+        return Ok(());
+    }
+
+    let term = Term::buffered_stdout();
+
+    for (index, action) in plan.actions.iter().enumerate() {
+        let mut meta = vec![];
+
+        if let Some(hash) = &action.hash {
+            meta.push(hash.clone());
+        }
+
+        if let Some(true) = action.cache_hit {
+            meta.push(String::from("cached"));
+        }
+
+        if meta.is_empty() {
+            term.write_line(&format!("{}. {}", index + 1, output::bold(&action.label)))?;
+        } else {
+            term.write_line(&format!(
+                "{}. {} {}",
+                index + 1,
+                output::bold(&action.label),
+                color::muted(&format!("({})", meta.join(", ")))
+            ))?;
+        }
+
+        if let Some(command) = &action.command {
+            term.write_line(&format!(
+                "   {} {}",
+                color::muted("$"),
+                color::shell(&format!("{} {}", command.bin, command.args.join(" ")))
+            ))?;
+
+            if let Some(cwd) = &command.cwd {
+                term.write_line(&format!("   {} {}", color::muted("in"), color::path(cwd)))?;
+            }
+
+            for (key, value) in &command.env {
+                term.write_line(&format!(
+                    "   {}{}{}",
+                    key,
+                    color::muted("="),
+                    color::muted_light(value)
+                ))?;
+            }
+        }
+    }
+
+    term.write_line("")?;
+    term.write_line(&color::muted("Dry run, no actions were ran"))?;
+    term.flush()?;
+
+    Ok(())
+}
+
 pub fn render_result_stats(
     results: Vec<Action>,
     duration: Duration,
@@ -76,6 +208,8 @@ pub fn render_result_stats(
     let mut cached_count = 0;
     let mut pass_count = 0;
     let mut fail_count = 0;
+    let mut skipped_count = 0;
+    let mut interrupted_count = 0;
     let mut invalid_count = 0;
 
     let filtered_results = if in_actions_context {
@@ -92,7 +226,7 @@ pub fn render_result_stats(
 
     for result in filtered_results {
         match result.status {
-            ActionStatus::Cached => {
+            ActionStatus::Cached | ActionStatus::CachedFromRemote => {
                 cached_count += 1;
                 pass_count += 1;
             }
@@ -102,6 +236,12 @@ pub fn render_result_stats(
             ActionStatus::Failed | ActionStatus::FailedAndAbort => {
                 fail_count += 1;
             }
+            ActionStatus::SkippedFromFailure => {
+                skipped_count += 1;
+            }
+            ActionStatus::Interrupted => {
+                interrupted_count += 1;
+            }
             ActionStatus::Invalid => {
                 invalid_count += 1;
             }
@@ -126,6 +266,17 @@ pub fn render_result_stats(
         counts_message.push(color::failure(&format!("{} failed", fail_count)));
     }
 
+    if skipped_count > 0 {
+        counts_message.push(color::muted_light(&format!("{} skipped", skipped_count)));
+    }
+
+    if interrupted_count > 0 {
+        counts_message.push(color::invalid(&format!(
+            "{} interrupted",
+            interrupted_count
+        )));
+    }
+
     if invalid_count > 0 {
         counts_message.push(color::invalid(&format!("{} invalid", invalid_count)));
     }
@@ -150,61 +301,355 @@ pub fn render_result_stats(
     Ok(())
 }
 
-pub async fn run(target_id: &str, options: RunOptions) -> Result<(), Box<dyn std::error::Error>> {
-    let target = Target::parse(target_id)?;
-    let workspace = Workspace::load().await?;
-
-    // Generate a dependency graph for all the targets that need to be ran
+/// Generate a dependency graph for all the targets that need to be ran,
+/// so that common dependencies are only ran once. Returns the graph
+/// and the primary targets that were inserted into it.
+pub fn generate_dep_graph(
+    workspace: &Workspace,
+    options: &RunOptions,
+    targets: &[Target],
+    touched_files: Option<&TouchedFilePaths>,
+    report_missing: bool,
+) -> Result<(DepGraph, Vec<String>), WorkspaceError> {
     let mut dep_graph = DepGraph::default();
-
-    if options.affected {
-        let touched_files =
-            get_touched_files(&workspace, &options.status, options.upstream).await?;
-        let inserted_count =
-            dep_graph.run_target(&target, &workspace.projects, Some(&touched_files))?;
-
-        if inserted_count == 0 {
-            if matches!(options.status, RunStatus::All) {
+    let mut primary_targets = vec![];
+
+    dep_graph.set_project_filter(ProjectFilter {
+        languages: options.language.iter().map(|l| l.into()).collect(),
+        tags: options.tags.clone(),
+        types: options.type_of.iter().map(|t| t.into()).collect(),
+    });
+
+    // Resolve `~:task` and `^:task` targets relative to the current project
+    dep_graph.set_working_project(
+        workspace
+            .projects
+            .find_owning_project(&workspace.working_dir),
+    );
+
+    for target in targets {
+        let inserted_targets = dep_graph.run_target(target, &workspace.projects, touched_files)?;
+
+        if inserted_targets.is_empty() && report_missing {
+            if touched_files.is_none() {
+                println!("No tasks found for target {}", color::target(&target.id));
+            } else if matches!(options.status, RunStatus::All) {
                 println!(
                     "Target {} not affected by touched files",
-                    color::target(target_id)
+                    color::target(&target.id)
                 );
             } else {
                 println!(
                     "Target {} not affected by touched files (using status {})",
-                    color::target(target_id),
+                    color::target(&target.id),
                     color::symbol(&options.status.to_string().to_lowercase())
                 );
             }
+        }
+
+        primary_targets.extend(inserted_targets);
+    }
+
+    if options.dependents && !primary_targets.is_empty() {
+        for target in targets {
+            dep_graph.run_target_dependents(target, &workspace.projects)?;
+        }
+    }
+
+    Ok((dep_graph, primary_targets))
+}
+
+/// Generate a dependency graph for the targets that are affected by changed files while
+/// watching, either directly or through one of their dependencies, along with their dependents.
+fn generate_watch_dep_graph(
+    workspace: &Workspace,
+    options: &RunOptions,
+    targets: &[Target],
+    changes: &TouchedFilePaths,
+) -> Result<(DepGraph, Vec<String>), WorkspaceError> {
+    let (dep_graph, primary_targets) =
+        generate_dep_graph(workspace, options, targets, None, false)?;
+    let mut affected_targets = vec![];
+
+    for target_id in primary_targets {
+        if dep_graph.is_target_affected(&target_id, &workspace.projects, changes)? {
+            affected_targets.push(Target::parse(&target_id)?);
+        }
+    }
+
+    let (mut dep_graph, primary_targets) =
+        generate_dep_graph(workspace, options, &affected_targets, None, false)?;
+
+    for target in &affected_targets {
+        dep_graph.run_target_dependents(target, &workspace.projects)?;
+    }
+
+    Ok((dep_graph, primary_targets))
+}
+
+/// Generate a dependency graph for restarting an interrupted run, for the targets that are
+/// affected by the changed files, and the primary targets of the interrupted run.
+fn generate_restart_dep_graph(
+    workspace: &Workspace,
+    options: &RunOptions,
+    targets: &[Target],
+    changes: &TouchedFilePaths,
+    primary_targets: Vec<String>,
+) -> Result<(DepGraph, Vec<String>), WorkspaceError> {
+    let (mut dep_graph, mut restart_targets) =
+        generate_watch_dep_graph(workspace, options, targets, changes)?;
+
+    for target_id in primary_targets {
+        if !restart_targets.contains(&target_id) {
+            restart_targets.extend(dep_graph.run_target(
+                &Target::parse(&target_id)?,
+                &workspace.projects,
+                None,
+            )?);
+        }
+    }
+
+    Ok((dep_graph, restart_targets))
+}
+
+/// Return the output paths of every target in the graph, as they are written while running.
+fn get_target_output_paths(
+    workspace: &Workspace,
+    dep_graph: &DepGraph,
+) -> Result<Vec<PathBuf>, WorkspaceError> {
+    let mut output_paths = vec![];
+
+    for node_index in dep_graph.graph.node_indices() {
+        if let Some(Node::RunTarget(target_id)) = dep_graph.get_node_from_index(node_index) {
+            let (project_id, task_id) = Target::parse(target_id)?.ids()?;
+            let project = workspace.projects.load(&project_id)?;
 
-            return Ok(());
+            output_paths.extend(project.get_task(&task_id)?.output_paths.iter().cloned());
         }
+    }
+
+    Ok(output_paths)
+}
+
+// Errors while watching (for example, an invalid config that is being edited)
+// should not stop the watcher, so report them and wait for the next change
+fn render_watch_error(error: WorkspaceError) {
+    println!("{} {}", color::failure("error"), error);
+    println!();
+}
+
+/// Keep the workspace loaded, and re-run targets (and their dependents)
+/// that are affected by files that have changed, until interrupted.
+async fn watch(
+    runner: &mut ActionRunner,
+    targets: &[Target],
+    options: &RunOptions,
+    dep_graph: DepGraph,
+    primary_targets: Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let workspace = runner.get_workspace();
+    let mut watcher = FileWatcher::new(&workspace.read().await.root)?;
+    let interrupter = runner.create_interrupter();
+    let mut next_run = if primary_targets.is_empty() {
+        None
     } else {
-        let inserted_count = dep_graph.run_target(&target, &workspace.projects, None)?;
+        Some((dep_graph, primary_targets))
+    };
+
+    loop {
+        let (dep_graph, primary_targets) = match next_run.take() {
+            Some(next) => next,
+            None => {
+                println!("{}", color::muted("Waiting for file changes..."));
+
+                // Only changes that affect a target will trigger a run
+                loop {
+                    let changes = tokio::select! {
+                        changes = watcher.next_changes(WATCH_DEBOUNCE) => changes,
+                        _ = tokio::signal::ctrl_c() => safe_exit(130),
+                    };
+
+                    let changes = match changes {
+                        Some(changes) => changes,
+                        None => return Ok(()),
+                    };
+
+                    match generate_watch_dep_graph(
+                        &*workspace.read().await,
+                        options,
+                        targets,
+                        &changes,
+                    ) {
+                        Ok(next) if !next.1.is_empty() => break next,
+                        Ok(_) => {}
+                        Err(error) => render_watch_error(error),
+                    }
+                }
+            }
+        };
+
+        // Targets that write outputs that are not ignored by git would otherwise restart themselves
+        match get_target_output_paths(&*workspace.read().await, &dep_graph) {
+            Ok(output_paths) => watcher.ignore_paths(output_paths),
+            Err(error) => render_watch_error(error),
+        };
+
+        runner.set_primary_targets(primary_targets.clone());
+
+        // Files that changed while running, and affect a target, will interrupt
+        // the run, and restart its primary targets alongside the affected targets
+        let mut restart_files: Option<TouchedFilePaths> = None;
+
+        let result = {
+            let run = runner.run(dep_graph);
+            tokio::pin!(run);
+
+            loop {
+                tokio::select! {
+                    result = &mut run => break result,
+                    Some(changes) = watcher.next_changes(WATCH_DEBOUNCE) => {
+                        let affected_targets = match generate_watch_dep_graph(
+                            &*workspace.read().await,
+                            options,
+                            targets,
+                            &changes,
+                        ) {
+                            Ok((_, affected_targets)) => affected_targets,
+                            Err(error) => {
+                                render_watch_error(error);
+                                continue;
+                            }
+                        };
+
+                        if affected_targets.is_empty() {
+                            continue;
+                        }
+
+                        match &mut restart_files {
+                            Some(files) => files.extend(changes),
+                            None => {
+                                restart_files = Some(changes);
+                                interrupter.send(RESTART_SIGNAL).ok();
+                            }
+                        }
+                    }
+                }
+            }
+        };
+
+        if let Some(files) = restart_files {
+            let workspace = workspace.read().await;
+
+            println!();
+            println!("{}", color::muted("Files changed, restarting..."));
+
+            next_run = match generate_restart_dep_graph(
+                &workspace,
+                options,
+                targets,
+                &files,
+                primary_targets,
+            ) {
+                Ok(next) => Some(next),
+                Err(error) => {
+                    render_watch_error(error);
+                    None
+                }
+            };
+
+            continue;
+        }
 
-        if inserted_count == 0 {
-            println!("No tasks found for target {}", color::target(target_id));
+        match result {
+            Ok(results) => {
+                render_failures(&results)?;
+                render_result_stats(results, runner.duration.unwrap(), false)?;
+            }
+            // Bailing only aborts the current run, and not the watcher
+            Err(WorkspaceError::ActionRunnerFailure(error)) => {
+                println!("{} {}", color::failure("fail"), error);
+                println!();
+            }
+            Err(error) => return Err(Box::new(error)),
+        }
 
-            return Ok(());
+        if runner.was_interrupted() {
+            safe_exit(130);
         }
     }
+}
+
+pub async fn run(
+    target_ids: &[String],
+    options: RunOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let targets = target_ids
+        .iter()
+        .map(|target_id| Target::parse(target_id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let workspace = Workspace::load().await?;
 
-    if options.dependents {
-        dep_graph.run_target_dependents(&target, &workspace.projects)?;
+    let touched_files = if options.affected {
+        Some(get_touched_files(&workspace, &options.status, options.upstream).await?)
+    } else {
+        None
+    };
+
+    let (dep_graph, primary_targets) =
+        generate_dep_graph(&workspace, &options, &targets, touched_files.as_ref(), true)?;
+
+    if primary_targets.is_empty() && !options.watch {
+        return Ok(());
     }
 
     // Process all tasks in the graph
     let mut runner = ActionRunner::new(workspace);
 
-    let results = runner
-        .bail_on_error()
-        .set_passthrough_args(options.passthrough)
-        .set_primary_target(target_id)
-        .run(dep_graph)
-        .await?;
+    if let Some(concurrency) = options.concurrency {
+        runner.set_concurrency(concurrency);
+    }
+
+    if !options.no_bail {
+        runner.bail_on_error();
+    }
+
+    runner.set_output_style(options.output_style.as_ref().map(TaskOutputStyle::from));
+    runner.set_profile(options.profile);
+
+    if let Some(report) = &options.report {
+        runner.set_report_path(env::current_dir()?.join(report));
+    }
+
+    runner
+        .set_explain(options.explain)
+        .set_passthrough_args(options.passthrough.clone())
+        .set_primary_targets(primary_targets.clone());
+
+    if options.dry_run {
+        render_run_plan(&runner.plan(&dep_graph).await?, options.json)?;
+
+        return Ok(());
+    }
+
+    if options.watch {
+        return watch(&mut runner, &targets, &options, dep_graph, primary_targets).await;
+    }
+
+    let results = runner.run(dep_graph).await?;
+
+    let failed = results.iter().any(|result| result.has_failed());
 
     // Render stats about the run
+    render_failures(&results)?;
     render_result_stats(results, runner.duration.unwrap(), false)?;
 
+    if runner.was_interrupted() {
+        safe_exit(130);
+    }
+
+    if failed {
+        safe_exit(1);
+    }
+
     Ok(())
 }
@@ -1,4 +1,5 @@
 use clap::ArgEnum;
+use moon_config::{ProjectLanguage as ConfigLanguage, ProjectType as ConfigType, TaskOutputStyle};
 use strum_macros::Display;
 
 #[derive(ArgEnum, Clone, Debug, Display)]
@@ -29,3 +30,57 @@ impl Default for LogLevel {
         LogLevel::Info
     }
 }
+
+#[derive(ArgEnum, Clone, Debug, Display)]
+pub enum OutputStyle {
+    Buffer,
+    Prefix,
+    Stream,
+}
+
+impl From<&OutputStyle> for TaskOutputStyle {
+    fn from(style: &OutputStyle) -> Self {
+        match style {
+            OutputStyle::Buffer => TaskOutputStyle::Buffer,
+            OutputStyle::Prefix => TaskOutputStyle::Prefix,
+            OutputStyle::Stream => TaskOutputStyle::Stream,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Debug, Display)]
+#[clap(rename_all = "lower")]
+pub enum ProjectLanguage {
+    JavaScript,
+    TypeScript,
+    Unknown,
+}
+
+impl From<&ProjectLanguage> for ConfigLanguage {
+    fn from(language: &ProjectLanguage) -> Self {
+        match language {
+            ProjectLanguage::JavaScript => ConfigLanguage::JavaScript,
+            ProjectLanguage::TypeScript => ConfigLanguage::TypeScript,
+            ProjectLanguage::Unknown => ConfigLanguage::Unknown,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Debug, Display)]
+pub enum ProjectType {
+    Application,
+    Library,
+    Tool,
+    Unknown,
+}
+
+impl From<&ProjectType> for ConfigType {
+    fn from(type_of: &ProjectType) -> Self {
+        match type_of {
+            ProjectType::Application => ConfigType::Application,
+            ProjectType::Library => ConfigType::Library,
+            ProjectType::Tool => ConfigType::Tool,
+            ProjectType::Unknown => ConfigType::Unknown,
+        }
+    }
+}
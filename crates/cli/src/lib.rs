@@ -5,14 +5,18 @@ mod helpers;
 
 use crate::commands::bin::bin;
 use crate::commands::ci::{ci, CiOptions};
+use crate::commands::clean::{clean, CleanOptions};
+use crate::commands::dep_graph::{dep_graph, DepGraphOptions};
+use crate::commands::hash::hash_diff;
 use crate::commands::init::{init, InitOptions};
+use crate::commands::log::log;
 use crate::commands::project::project;
 use crate::commands::project_graph::project_graph;
 use crate::commands::run::{run, RunOptions};
 use crate::commands::setup::setup;
 use crate::commands::teardown::teardown;
 use crate::helpers::setup_colors;
-use app::{App, Commands};
+use app::{App, Commands, HashCommands};
 use clap::Parser;
 use console::Term;
 use enums::LogLevel;
@@ -59,17 +63,39 @@ pub async fn run_cli() {
         Commands::Ci {
             base,
             head,
+            dry_run,
+            json,
+            concurrency,
             job,
             job_total,
+            output_style,
+            profile,
+            report,
         } => {
             ci(CiOptions {
                 base: base.clone(),
                 head: head.clone(),
+                dry_run: *dry_run,
+                json: *json,
+                concurrency: *concurrency,
                 job: *job,
                 job_total: *job_total,
+                output_style: output_style.clone(),
+                profile: *profile,
+                report: report.clone(),
             })
             .await
         }
+        Commands::Clean { max_age, max_size } => {
+            clean(CleanOptions {
+                max_age: *max_age,
+                max_size: *max_size,
+            })
+            .await
+        }
+        Commands::Hash { command } => match command {
+            HashCommands::Diff { left, right } => hash_diff(left, right).await,
+        },
         Commands::Init {
             dest,
             force,
@@ -88,24 +114,69 @@ pub async fn run_cli() {
             )
             .await
         }
+        Commands::Log { target } => log(target).await,
         Commands::Project { id, json } => project(id, *json).await,
         Commands::ProjectGraph { id } => project_graph(id).await,
+        Commands::DepGraph {
+            targets,
+            dependents,
+            json,
+            language,
+            tags,
+            type_of,
+        } => {
+            dep_graph(
+                targets,
+                DepGraphOptions {
+                    dependents: *dependents,
+                    json: *json,
+                    language: language.clone(),
+                    tags: tags.clone(),
+                    type_of: type_of.clone(),
+                },
+            )
+            .await
+        }
         Commands::Run {
-            target,
+            targets,
             affected,
+            concurrency,
             dependents,
+            dry_run,
+            explain,
+            json,
+            language,
+            no_bail,
+            output_style,
+            profile,
+            report,
+            tags,
+            type_of,
             status,
             passthrough,
             upstream,
+            watch,
         } => {
             run(
-                target,
+                targets,
                 RunOptions {
                     affected: *affected,
+                    concurrency: *concurrency,
                     dependents: *dependents,
+                    dry_run: *dry_run,
+                    explain: *explain,
+                    json: *json,
+                    language: language.clone(),
+                    no_bail: *no_bail,
+                    output_style: output_style.clone(),
+                    profile: *profile,
+                    report: report.clone(),
+                    tags: tags.clone(),
+                    type_of: type_of.clone(),
                     status: status.clone(),
                     passthrough: passthrough.clone(),
                     upstream: *upstream,
+                    watch: *watch,
                 },
             )
             .await
@@ -0,0 +1,55 @@
+use filetime::{set_file_mtime, FileTime};
+use insta::assert_snapshot;
+use moon_utils::test::{create_fixtures_sandbox, create_moon_command_in, get_assert_output};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+fn create_hash(root: &Path, hash: &str, days_old: u64) {
+    let cache_dir = root.join(".moon/cache");
+    let manifest = cache_dir.join(format!("hashes/{}.json", hash));
+
+    fs::create_dir_all(cache_dir.join("hashes")).unwrap();
+    fs::create_dir_all(cache_dir.join("out")).unwrap();
+    fs::write(&manifest, "{}").unwrap();
+    fs::write(cache_dir.join(format!("out/{}.tar.zst", hash)), "").unwrap();
+
+    set_file_mtime(
+        &manifest,
+        FileTime::from_system_time(SystemTime::now() - Duration::from_secs(days_old * 86400)),
+    )
+    .unwrap();
+}
+
+#[test]
+fn deletes_stale_hashes() {
+    let fixture = create_fixtures_sandbox("cases");
+
+    create_hash(fixture.path(), "old", 10);
+    create_hash(fixture.path(), "new", 0);
+
+    let assert = create_moon_command_in(fixture.path()).arg("clean").assert();
+
+    assert_snapshot!(get_assert_output(&assert));
+
+    assert!(!fixture.path().join(".moon/cache/hashes/old.json").exists());
+    assert!(!fixture.path().join(".moon/cache/out/old.tar.zst").exists());
+    assert!(fixture.path().join(".moon/cache/hashes/new.json").exists());
+    assert!(fixture.path().join(".moon/cache/out/new.tar.zst").exists());
+}
+
+#[test]
+fn can_override_max_age() {
+    let fixture = create_fixtures_sandbox("cases");
+
+    create_hash(fixture.path(), "old", 10);
+
+    create_moon_command_in(fixture.path())
+        .arg("clean")
+        .arg("--maxAge")
+        .arg("30")
+        .assert()
+        .success();
+
+    assert!(fixture.path().join(".moon/cache/hashes/old.json").exists());
+}
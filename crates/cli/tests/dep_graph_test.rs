@@ -0,0 +1,38 @@
+use moon_utils::test::create_moon_command;
+use predicates::prelude::*;
+
+#[test]
+fn prints_graph_in_dot_format() {
+    create_moon_command("cases")
+        .arg("dep-graph")
+        .arg("system:dependsOnPersistent")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("digraph {"))
+        .stdout(predicate::str::contains(r#"\"SyncProject(system)\""#))
+        .stdout(predicate::str::contains(
+            r#"\"RunTarget(system:persistent)\""#,
+        ))
+        .stdout(predicate::str::contains(
+            r#"\"RunTarget(system:dependsOnPersistent)\""#,
+        ));
+}
+
+#[test]
+fn prints_graph_in_json_format() {
+    create_moon_command("cases")
+        .arg("dep-graph")
+        .arg("system:dependsOnPersistent")
+        .arg("--json")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("{"))
+        .stdout(predicate::str::contains(r#""batches""#))
+        .stdout(predicate::str::contains(r#""edges""#))
+        .stdout(predicate::str::contains(
+            r#""label": "RunTarget(system:dependsOnPersistent)""#,
+        ))
+        .stdout(predicate::str::contains(
+            r#""label": "RunTarget(system:persistent)""#,
+        ));
+}
@@ -0,0 +1,72 @@
+use moon_utils::test::{create_fixtures_sandbox, create_moon_command_in};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+
+fn create_manifest(root: &Path, hash: &str, contents: &str) {
+    let hashes_dir = root.join(".moon/cache/hashes");
+
+    fs::create_dir_all(&hashes_dir).unwrap();
+    fs::write(hashes_dir.join(format!("{}.json", hash)), contents).unwrap();
+}
+
+#[test]
+fn lists_differences_between_hashes() {
+    let fixture = create_fixtures_sandbox("cases");
+
+    create_manifest(
+        fixture.path(),
+        "a",
+        r#"{"args":["--foo"],"envVars":{"KEY":"1"},"nodeVersion":"16.0.0"}"#,
+    );
+    create_manifest(
+        fixture.path(),
+        "b",
+        r#"{"args":["--bar"],"envVars":{"KEY":"2"},"nodeVersion":"16.0.0"}"#,
+    );
+
+    create_moon_command_in(fixture.path())
+        .arg("hash")
+        .arg("diff")
+        .arg("a")
+        .arg("b")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("args: added --bar"))
+        .stdout(predicate::str::contains("args: removed --foo"))
+        .stdout(predicate::str::contains("envVars[KEY]: 1 -> 2"))
+        .stdout(predicate::str::contains("nodeVersion").not());
+}
+
+#[test]
+fn reports_identical_hashes() {
+    let fixture = create_fixtures_sandbox("cases");
+
+    create_manifest(fixture.path(), "a", r#"{"args":["--foo"]}"#);
+    create_manifest(fixture.path(), "b", r#"{"args":["--foo"]}"#);
+
+    create_moon_command_in(fixture.path())
+        .arg("hash")
+        .arg("diff")
+        .arg("a")
+        .arg("b")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No differences between a and b"));
+}
+
+#[test]
+fn errors_for_missing_manifest() {
+    let fixture = create_fixtures_sandbox("cases");
+
+    create_manifest(fixture.path(), "a", "{}");
+
+    create_moon_command_in(fixture.path())
+        .arg("hash")
+        .arg("diff")
+        .arg("a")
+        .arg("unknown")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("manifest for hash unknown"));
+}
@@ -0,0 +1,92 @@
+use moon_utils::test::{create_fixtures_sandbox, create_moon_command_in};
+use predicates::prelude::*;
+
+#[test]
+fn errors_if_target_has_no_logs() {
+    let fixture = create_fixtures_sandbox("cases");
+
+    create_moon_command_in(fixture.path())
+        .arg("log")
+        .arg("system:echo")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No logs found for target"));
+}
+
+#[test]
+fn errors_for_scoped_targets() {
+    let fixture = create_fixtures_sandbox("cases");
+
+    create_moon_command_in(fixture.path())
+        .arg("log")
+        .arg("^:echo")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "requires literal project and task",
+        ));
+}
+
+#[cfg(not(windows))]
+mod system {
+    use super::*;
+
+    #[test]
+    fn prints_last_run_log() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .assert()
+            .success();
+
+        create_moon_command_in(fixture.path())
+            .arg("log")
+            .arg("system:echo")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Action: RunTarget(system:echo)"))
+            .stdout(predicate::str::contains("Status: passed"))
+            .stdout(predicate::str::contains("==> Attempt 1"))
+            .stdout(predicate::str::contains("Exit code: 0"))
+            .stdout(predicate::str::contains("hello"));
+    }
+
+    #[test]
+    fn includes_output_of_every_attempt() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:retryCount")
+            .assert()
+            .failure();
+
+        create_moon_command_in(fixture.path())
+            .arg("log")
+            .arg("system:retryCount")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Status: failed"))
+            .stdout(predicate::str::contains("==> Attempt 4"))
+            .stdout(predicate::str::contains("Exit code: 1"))
+            .stdout(predicate::str::contains("--- stderr ---\nstderr"));
+    }
+
+    #[test]
+    fn writes_logs_for_other_actions() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .assert()
+            .success();
+
+        let runs_dir = fixture.path().join(".moon/cache/runs");
+
+        assert!(runs_dir.join("_setupToolchain").exists());
+        assert!(runs_dir.join("system/_syncProject").exists());
+    }
+}
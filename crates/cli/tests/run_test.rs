@@ -178,6 +178,272 @@ mod caching {
     }
 }
 
+mod reporting {
+    use super::*;
+
+    #[test]
+    fn writes_run_report_to_cache() {
+        let fixture = create_fixtures_sandbox("cases");
+        let report_path = fixture.path().join(".moon/cache/runReport.json");
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("node:standard")
+            .assert();
+
+        let report = read_to_string(&report_path).unwrap();
+
+        assert!(predicate::str::contains(r#""label": "SetupToolchain""#).eval(&report));
+        assert!(predicate::str::contains(r#""label": "RunTarget(node:standard)""#).eval(&report));
+        assert!(predicate::str::contains(r#""status": "passed""#).eval(&report));
+        assert!(predicate::str::contains(r#""cacheHit": false"#).eval(&report));
+        assert!(predicate::str::contains(r#""exitCode": 0"#).eval(&report));
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("node:standard")
+            .assert();
+
+        let report = read_to_string(&report_path).unwrap();
+
+        assert!(predicate::str::contains(r#""status": "cached""#).eval(&report));
+        assert!(predicate::str::contains(r#""cacheHit": true"#).eval(&report));
+    }
+
+    #[test]
+    fn writes_run_report_to_custom_path() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("node:standard")
+            .arg("--report")
+            .arg("reports/run.json")
+            .assert();
+
+        let report = read_to_string(fixture.path().join("reports/run.json")).unwrap();
+
+        assert!(predicate::str::contains(r#""label": "RunTarget(node:standard)""#).eval(&report));
+    }
+
+    #[test]
+    fn writes_profile_when_enabled() {
+        let fixture = create_fixtures_sandbox("cases");
+        let profile_path = fixture.path().join(".moon/cache/runProfile.json");
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("node:standard")
+            .assert();
+
+        assert!(!profile_path.exists());
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("node:standard")
+            .arg("--profile")
+            .assert();
+
+        let profile = read_to_string(&profile_path).unwrap();
+
+        assert!(predicate::str::contains(r#""traceEvents":"#).eval(&profile));
+        assert!(predicate::str::contains(r#""name":"RunTarget(node:standard)""#).eval(&profile));
+        assert!(predicate::str::contains(r#""ph":"X""#).eval(&profile));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn writes_run_report_when_bailing() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:dependsOnFailure")
+            .assert()
+            .failure();
+
+        let report = read_to_string(fixture.path().join(".moon/cache/runReport.json")).unwrap();
+
+        assert!(
+            predicate::str::contains(r#""label": "RunTarget(system:exitNonZero)""#).eval(&report)
+        );
+        assert!(predicate::str::contains(r#""status": "failed""#).eval(&report));
+    }
+}
+
+#[cfg(not(windows))]
+mod dry_run {
+    use super::*;
+
+    #[test]
+    fn prints_plan_without_running() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .arg("--dryRun")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.success();
+        assert!(predicate::str::contains("1. SetupToolchain").eval(&output));
+        assert!(predicate::str::contains("RunTarget(system:echo)").eval(&output));
+        assert!(predicate::str::contains("$ echo hello").eval(&output));
+        assert!(predicate::str::contains("MOON_TARGET=system:echo").eval(&output));
+        assert!(predicate::str::contains("Dry run, no actions were ran").eval(&output));
+
+        assert!(!fixture
+            .path()
+            .join(".moon/cache/runs/system/echo/lastRunState.json")
+            .exists());
+    }
+
+    #[test]
+    fn prints_plan_as_json() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .arg("--dry-run")
+            .arg("--json")
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with("{"))
+            .stdout(predicate::str::contains(
+                r#""label": "RunTarget(system:echo)""#,
+            ))
+            .stdout(predicate::str::contains(r#""bin": "echo""#))
+            .stdout(predicate::str::contains(r#""cacheHit": false"#));
+    }
+
+    #[test]
+    fn marks_cached_targets() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .assert()
+            .success();
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .arg("--dryRun")
+            .arg("--json")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#""cacheHit": true"#));
+    }
+}
+
+#[cfg(not(windows))]
+mod watch {
+    use super::*;
+    use std::fs;
+    use std::thread;
+    use std::time::Duration;
+
+    // Watching never exits on its own, so write files from another
+    // thread, and kill the process once it has had time to re-run
+    fn write_file_after(path: &Path, delay: u64) -> thread::JoinHandle<()> {
+        let path = path.to_path_buf();
+
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(delay));
+            fs::write(path, "changed").unwrap();
+        })
+    }
+
+    #[test]
+    fn errors_with_dry_run() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .arg("--watch")
+            .arg("--dryRun")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+
+    #[test]
+    fn runs_and_waits_for_changes() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .arg("--watch")
+            .timeout(Duration::from_secs(5))
+            .assert();
+        let output = get_assert_output(&assert);
+
+        assert!(predicate::str::contains("hello").eval(&output));
+        assert!(predicate::str::contains("Tasks: 1 completed").eval(&output));
+        assert_eq!(output.matches("Waiting for file changes").count(), 1);
+    }
+
+    #[test]
+    fn reruns_when_affected_files_change() {
+        let fixture = create_fixtures_sandbox("cases");
+        let writer = write_file_after(&fixture.path().join("system/changed.txt"), 2000);
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .arg("--watch")
+            .timeout(Duration::from_secs(6))
+            .assert();
+        let output = get_assert_output(&assert);
+
+        writer.join().unwrap();
+
+        assert_eq!(output.matches("Waiting for file changes").count(), 2);
+    }
+
+    #[test]
+    fn reruns_when_dependency_files_change() {
+        let fixture = create_fixtures_sandbox("cases");
+        let writer = write_file_after(&fixture.path().join("deps-c/changed.txt"), 2000);
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("depsA:dependencyOrder")
+            .arg("--watch")
+            .timeout(Duration::from_secs(6))
+            .assert();
+        let output = get_assert_output(&assert);
+
+        writer.join().unwrap();
+
+        assert_eq!(output.matches("Waiting for file changes").count(), 2);
+    }
+
+    #[test]
+    fn doesnt_rerun_when_unaffected_files_change() {
+        let fixture = create_fixtures_sandbox("cases");
+        let writer = write_file_after(&fixture.path().join("base/changed.txt"), 2000);
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .arg("--watch")
+            .timeout(Duration::from_secs(6))
+            .assert();
+        let output = get_assert_output(&assert);
+
+        writer.join().unwrap();
+
+        assert_eq!(output.matches("Waiting for file changes").count(), 1);
+    }
+}
+
 mod dependencies {
     use super::*;
 
@@ -204,6 +470,141 @@ mod dependencies {
 
         assert_snapshot!(get_assert_output(&assert));
     }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn bails_on_first_failure() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:dependsOnFailure")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.failure();
+        assert!(predicate::str::contains("should not run")
+            .not()
+            .eval(&output));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn skips_dependents_of_failures_when_not_bailing() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:dependsOnFailure")
+            .arg("--noBail")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.failure();
+        assert!(predicate::str::contains("should not run")
+            .not()
+            .eval(&output));
+        assert!(predicate::str::contains("fail RunTarget(system:exitNonZero)").eval(&output));
+        assert!(predicate::str::contains(
+            "skip RunTarget(system:dependsOnFailure) (Skipped because its dependency RunTarget(system:exitNonZero) failed)"
+        )
+        .eval(&output));
+        assert!(predicate::str::contains("1 failed, 1 skipped").eval(&output));
+    }
+}
+
+#[cfg(not(windows))]
+mod multiple_targets {
+    use super::*;
+
+    #[test]
+    fn runs_all_targets_in_one_graph() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .arg("system:ls")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.success();
+        assert!(predicate::str::contains("system:echo").eval(&output));
+        assert!(predicate::str::contains("system:ls").eval(&output));
+        assert!(predicate::str::contains("Tasks: 2 completed").eval(&output));
+    }
+
+    #[test]
+    fn runs_shared_dependencies_once() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:passthroughArgsWithDep")
+            .arg("system:passthroughArgs")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.success();
+        assert!(predicate::str::contains("Tasks: 2 completed").eval(&output));
+    }
+
+    #[test]
+    fn supports_all_scope_with_other_targets() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg(":all")
+            .arg("system:echo")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert!(predicate::str::contains("targetScopeA:all").eval(&output));
+        assert!(predicate::str::contains("system:echo").eval(&output));
+        assert!(predicate::str::contains("Tasks: 4 completed").eval(&output));
+    }
+
+    #[test]
+    fn passes_args_only_to_listed_targets() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:passthroughArgsWithDep")
+            .arg("--")
+            .arg("extra")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.success();
+        assert!(predicate::str::contains("primary extra").eval(&output));
+        assert_eq!(output.matches("extra").count(), 1);
+    }
+
+    #[test]
+    fn fails_if_any_target_fails() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .arg("system:exitNonZero")
+            .arg("--noBail")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.failure().code(1);
+        assert!(predicate::str::contains("1 completed").eval(&output));
+        assert!(predicate::str::contains("1 failed").eval(&output));
+    }
 }
 
 mod target_scopes {
@@ -229,6 +630,69 @@ mod target_scopes {
         assert_snapshot!(get_assert_output(&assert));
     }
 
+    #[test]
+    fn supports_deps_scope_in_project() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(&fixture.path().join("target-scope-a"))
+            .arg("run")
+            .arg("^:standard")
+            .assert();
+        let output = get_assert_output(&assert);
+
+        assert!(predicate::str::contains("depsA:standard").eval(&output));
+        assert!(predicate::str::contains("depsB:standard").eval(&output));
+        assert!(predicate::str::contains("depsC:standard").eval(&output));
+        assert!(predicate::str::contains("Tasks: 3 completed").eval(&output));
+    }
+
+    #[test]
+    fn supports_self_scope_in_project() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(&fixture.path().join("target-scope-b"))
+            .arg("run")
+            .arg("~:self")
+            .assert();
+        let output = get_assert_output(&assert);
+
+        assert!(predicate::str::contains("targetScopeB:self").eval(&output));
+        assert!(predicate::str::contains("scope=self").eval(&output));
+        assert!(predicate::str::contains("Tasks: 2 completed").eval(&output));
+    }
+
+    #[test]
+    fn supports_all_tasks_of_project() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("targetScopeB:*")
+            .assert();
+        let output = get_assert_output(&assert);
+
+        assert!(predicate::str::contains("targetScopeB:all").eval(&output));
+        assert!(predicate::str::contains("targetScopeB:self").eval(&output));
+        assert!(predicate::str::contains("targetScopeB:selfOther").eval(&output));
+        assert!(predicate::str::contains("Tasks: 3 completed").eval(&output));
+    }
+
+    #[test]
+    fn supports_task_globs() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("targetScopeB:self*")
+            .assert();
+        let output = get_assert_output(&assert);
+
+        assert!(!predicate::str::contains("targetScopeB:all").eval(&output));
+        assert!(predicate::str::contains("targetScopeB:self").eval(&output));
+        assert!(predicate::str::contains("targetScopeB:selfOther").eval(&output));
+        assert!(predicate::str::contains("Tasks: 2 completed").eval(&output));
+    }
+
     #[test]
     fn supports_all_scope() {
         let fixture = create_fixtures_sandbox("cases");
@@ -245,6 +709,77 @@ mod target_scopes {
         assert!(predicate::str::contains("Tasks: 3 completed").eval(&output));
     }
 
+    #[test]
+    fn filters_all_scope_by_project_type() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg(":all")
+            .arg("--type")
+            .arg("application")
+            .assert();
+        let output = get_assert_output(&assert);
+
+        assert!(predicate::str::contains("targetScopeA:all")
+            .not()
+            .eval(&output));
+        assert!(predicate::str::contains("targetScopeB:all").eval(&output));
+        assert!(predicate::str::contains("targetScopeC:all")
+            .not()
+            .eval(&output));
+        assert!(predicate::str::contains("Tasks: 1 completed").eval(&output));
+    }
+
+    #[test]
+    fn filters_all_scope_by_project_language() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg(":all")
+            .arg("--language")
+            .arg("javascript")
+            .assert();
+        let output = get_assert_output(&assert);
+
+        assert!(predicate::str::contains("targetScopeC:all").eval(&output));
+        assert!(predicate::str::contains("Tasks: 1 completed").eval(&output));
+    }
+
+    #[test]
+    fn filters_all_scope_by_project_tags() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg(":all")
+            .arg("--tag")
+            .arg("ui")
+            .arg("--tag")
+            .arg("other")
+            .assert();
+        let output = get_assert_output(&assert);
+
+        assert!(predicate::str::contains("targetScopeB:all").eval(&output));
+        assert!(predicate::str::contains("Tasks: 1 completed").eval(&output));
+    }
+
+    #[test]
+    fn prints_when_no_projects_match_filters() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg(":all")
+            .arg("--type")
+            .arg("tool")
+            .assert();
+
+        assert!(predicate::str::contains("No tasks found for target :all")
+            .eval(&get_assert_output(&assert)));
+    }
+
     #[test]
     fn supports_deps_scope_in_task() {
         let fixture = create_fixtures_sandbox("cases");
@@ -974,6 +1509,158 @@ mod system {
 
         assert_snapshot!(get_assert_output(&assert));
     }
+
+    #[test]
+    fn prefixes_output_when_style_is_prefix() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:echo")
+            .arg("--outputStyle")
+            .arg("prefix")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.success();
+        assert!(predicate::str::contains("system:echo | hello").eval(&output));
+    }
+
+    #[test]
+    fn prefixes_output_with_retry_attempts() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:retryCount")
+            .arg("--output-style")
+            .arg("prefix")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.failure();
+        assert!(predicate::str::contains("system:retryCount | stdout").eval(&output));
+        assert!(predicate::str::contains("system:retryCount (2/4) | stdout").eval(&output));
+    }
+
+    #[test]
+    fn prefixes_output_when_task_option_is_prefix() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:outputStylePrefix")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.success();
+        assert!(predicate::str::contains("system:outputStylePrefix | prefixed").eval(&output));
+    }
+
+    #[test]
+    fn buffers_output_when_style_is_buffer() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:outputStylePrefix")
+            .arg("--outputStyle")
+            .arg("buffer")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.success();
+        assert!(predicate::str::contains("prefixed").eval(&output));
+        assert!(predicate::str::contains("| prefixed").not().eval(&output));
+    }
+
+    #[test]
+    fn kills_process_when_timed_out() {
+        use std::time::{Duration, Instant};
+
+        let fixture = create_fixtures_sandbox("cases");
+        let start = Instant::now();
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:timeout")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.failure();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(predicate::str::contains("timed out after 1 seconds").eval(&output));
+        assert!(predicate::str::contains("This should not appear!")
+            .not()
+            .eval(&output));
+    }
+
+    #[test]
+    fn doesnt_wait_for_persistent_deps() {
+        use std::time::{Duration, Instant};
+
+        let fixture = create_fixtures_sandbox("cases");
+        let start = Instant::now();
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:dependsOnPersistent")
+            .timeout(Duration::from_secs(20))
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.success();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(predicate::str::contains("persistent dependent").eval(&output));
+        assert!(predicate::str::contains("Tasks: 2 completed").eval(&output));
+    }
+
+    #[test]
+    fn never_runs_tasks_with_same_mutex_in_parallel() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:mutexA")
+            .arg("system:mutexB")
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.success();
+        assert!(predicate::str::contains("released a").eval(&output));
+        assert!(predicate::str::contains("released b").eval(&output));
+        assert!(!predicate::str::contains("resource is already held").eval(&output));
+    }
+
+    #[test]
+    fn never_caches_persistent_deps() {
+        let fixture = create_fixtures_sandbox("cases");
+
+        create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:dependsOnPersistent")
+            .timeout(std::time::Duration::from_secs(20))
+            .assert()
+            .success();
+
+        let assert = create_moon_command_in(fixture.path())
+            .arg("run")
+            .arg("system:dependsOnPersistent")
+            .timeout(std::time::Duration::from_secs(20))
+            .assert();
+
+        let output = get_assert_output(&assert);
+
+        assert.success();
+        assert!(predicate::str::contains("Tasks: 2 completed (1 cached)").eval(&output));
+    }
 }
 
 #[cfg(windows)]
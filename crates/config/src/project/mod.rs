@@ -46,6 +46,14 @@ fn validate_tasks(map: &HashMap<String, TaskConfig>) -> Result<(), ValidationErr
     Ok(())
 }
 
+fn validate_tags(list: &[String]) -> Result<(), ValidationError> {
+    for (index, item) in list.iter().enumerate() {
+        validate_id(&format!("tags[{}]", index), item)?;
+    }
+
+    Ok(())
+}
+
 fn validate_channel(value: &str) -> Result<(), ValidationError> {
     if !value.is_empty() && !value.starts_with('#') {
         return Err(create_validation_error(
@@ -136,6 +144,10 @@ pub struct ProjectConfig {
     #[validate]
     pub project: Option<ProjectMetadataConfig>,
 
+    #[serde(default)]
+    #[validate(custom = "validate_tags")]
+    pub tags: Vec<String>,
+
     #[serde(default)]
     #[validate(custom = "validate_tasks")]
     #[validate]
@@ -405,6 +417,27 @@ tasks:
                 Ok(())
             });
         }
+
+        #[test]
+        #[should_panic(expected = "Invalid field <id>deps[0]</id>: Must be a valid target format.")]
+        fn invalid_wildcard_deps() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(
+                    super::constants::CONFIG_PROJECT_FILENAME,
+                    r#"
+tasks:
+    test:
+        command: jest
+        deps:
+            - 'foo:*'
+"#,
+                )?;
+
+                super::load_jailed_config()?;
+
+                Ok(())
+            });
+        }
     }
 
     mod project {
@@ -560,6 +593,40 @@ project:
         }
     }
 
+    mod tags {
+        #[test]
+        #[should_panic(
+            expected = "Invalid field <id>tags</id>: Expected a sequence type, received unsigned int `123`."
+        )]
+        fn invalid_type() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(super::constants::CONFIG_PROJECT_FILENAME, "tags: 123")?;
+
+                super::load_jailed_config()?;
+
+                Ok(())
+            });
+        }
+
+        #[test]
+        #[should_panic(expected = "Invalid field <id>tags[1]</id>: Must be a valid ID.")]
+        fn invalid_id() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(
+                    super::constants::CONFIG_PROJECT_FILENAME,
+                    r#"
+tags:
+    - ui
+    - '@frontend'"#,
+                )?;
+
+                super::load_jailed_config()?;
+
+                Ok(())
+            });
+        }
+    }
+
     mod workspace {
         #[test]
         #[should_panic(
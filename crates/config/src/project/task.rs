@@ -1,5 +1,5 @@
 use crate::types::{FilePath, FilePathOrGlob, TargetID};
-use crate::validators::{validate_child_or_root_path, validate_target};
+use crate::validators::{validate_child_or_root_path, validate_id, validate_target};
 use schemars::gen::SchemaGenerator;
 use schemars::schema::Schema;
 use schemars::{schema_for, JsonSchema};
@@ -28,6 +28,14 @@ fn validate_inputs(list: &[String]) -> Result<(), ValidationError> {
     Ok(())
 }
 
+fn validate_mutex(list: &[String]) -> Result<(), ValidationError> {
+    for (index, item) in list.iter().enumerate() {
+        validate_id(&format!("mutex[{}]", index), item)?;
+    }
+
+    Ok(())
+}
+
 fn validate_outputs(list: &[String]) -> Result<(), ValidationError> {
     for (index, item) in list.iter().enumerate() {
         validate_child_or_root_path(&format!("outputs[{}]", index), item)?;
@@ -63,9 +71,19 @@ impl Default for TaskMergeStrategy {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskOutputStyle {
+    Buffer,
+    Prefix,
+    Stream,
+}
+
 #[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct TaskOptionsConfig {
+    pub cache: Option<bool>,
+
     pub merge_args: Option<TaskMergeStrategy>,
 
     pub merge_deps: Option<TaskMergeStrategy>,
@@ -76,25 +94,46 @@ pub struct TaskOptionsConfig {
 
     pub merge_outputs: Option<TaskMergeStrategy>,
 
+    /// Names of shared resources, like a database or port. Tasks that
+    /// share a resource are never ran at the same time.
+    #[validate(custom = "validate_mutex")]
+    pub mutex: Option<Vec<String>>,
+
+    /// How the task's output is displayed while running. When not defined,
+    /// the primary target is streamed and all other targets are buffered.
+    pub output_style: Option<TaskOutputStyle>,
+
+    /// Long-running process that never completes, like a watcher or server.
+    /// Dependents do not wait for it, and it's shut down once they exit.
+    pub persistent: Option<bool>,
+
     pub retry_count: Option<u8>,
 
     #[serde(rename = "runInCI")]
     pub run_in_ci: Option<bool>,
 
     pub run_from_workspace_root: Option<bool>,
+
+    /// Max duration of the task's process, in seconds.
+    pub timeout: Option<u64>,
 }
 
 impl Default for TaskOptionsConfig {
     fn default() -> Self {
         TaskOptionsConfig {
+            cache: Some(true),
             merge_args: Some(TaskMergeStrategy::default()),
             merge_deps: Some(TaskMergeStrategy::default()),
             merge_env: Some(TaskMergeStrategy::default()),
             merge_inputs: Some(TaskMergeStrategy::default()),
             merge_outputs: Some(TaskMergeStrategy::default()),
+            mutex: None,
+            output_style: None,
+            persistent: Some(false),
             retry_count: Some(0),
             run_in_ci: Some(true),
             run_from_workspace_root: Some(false),
+            timeout: None,
         }
     }
 }
@@ -606,6 +645,69 @@ options:
             });
         }
 
+        #[test]
+        #[should_panic(
+            expected = "Invalid field <id>options.mutex</id>: Expected a sequence type, received string \"db\"."
+        )]
+        fn invalid_mutex_type() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(
+                    super::CONFIG_FILENAME,
+                    r#"
+command: foo
+options:
+    mutex: db
+"#,
+                )?;
+
+                super::load_jailed_config()?;
+
+                Ok(())
+            });
+        }
+
+        #[test]
+        #[should_panic(
+            expected = "Invalid field <id>options.outputStyle</id>: Unknown option <id>fancy</id>."
+        )]
+        fn invalid_output_style_type() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(
+                    super::CONFIG_FILENAME,
+                    r#"
+command: foo
+options:
+    outputStyle: fancy
+"#,
+                )?;
+
+                super::load_jailed_config()?;
+
+                Ok(())
+            });
+        }
+
+        #[test]
+        #[should_panic(
+            expected = "Invalid field <id>options.persistent</id>: Expected a boolean type, received unsigned int `123`."
+        )]
+        fn invalid_persistent_type() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(
+                    super::CONFIG_FILENAME,
+                    r#"
+command: foo
+options:
+    persistent: 123
+"#,
+                )?;
+
+                super::load_jailed_config()?;
+
+                Ok(())
+            });
+        }
+
         #[test]
         #[should_panic(
             expected = "Invalid field <id>options.retryCount</id>: Expected u8 type, received string \"abc\"."
@@ -118,3 +118,16 @@ pub fn validate_target(key: &str, target_id: &str) -> Result<(), ValidationError
 
     Ok(())
 }
+
+// Validate the value is an HTTP(S) URL.
+pub fn validate_url(key: &str, value: &str) -> Result<(), ValidationError> {
+    if !value.starts_with("http://") && !value.starts_with("https://") {
+        return Err(create_validation_error(
+            "invalid_url",
+            key,
+            String::from("Must be a valid URL, starting with http:// or https://."),
+        ));
+    }
+
+    Ok(())
+}
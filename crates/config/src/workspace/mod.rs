@@ -5,9 +5,11 @@ mod typescript;
 mod vcs;
 
 use crate::constants;
-use crate::errors::map_figment_error_to_validation_errors;
+use crate::errors::{create_validation_error, map_figment_error_to_validation_errors};
 use crate::types::{FileGlob, FilePath};
-use crate::validators::{default_bool_true, validate_child_relative_path, validate_id};
+use crate::validators::{
+    default_bool_true, validate_child_relative_path, validate_id, validate_url,
+};
 use figment::value::{Dict, Map};
 use figment::{
     providers::{Format, Serialized, Yaml},
@@ -49,9 +51,28 @@ fn validate_projects(projects: &ProjectsMap) -> Result<(), ValidationError> {
     Ok(())
 }
 
+fn validate_concurrency(value: &usize) -> Result<(), ValidationError> {
+    if *value == 0 {
+        return Err(create_validation_error(
+            "invalid_concurrency",
+            "actionRunner.concurrency",
+            String::from("Must be greater than 0."),
+        ));
+    }
+
+    Ok(())
+}
+
 #[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct ActionRunnerConfig {
+    /// Max number of actions to run in parallel. Defaults to the number of CPUs.
+    #[validate(custom = "validate_concurrency")]
+    pub concurrency: Option<usize>,
+
+    /// Max duration of a task's process, in seconds, when not configured by the task itself.
+    pub default_timeout: Option<u64>,
+
     #[serde(default = "default_bool_true")]
     pub inherit_colors_for_piped_tasks: bool,
 }
@@ -59,11 +80,52 @@ pub struct ActionRunnerConfig {
 impl Default for ActionRunnerConfig {
     fn default() -> Self {
         ActionRunnerConfig {
+            concurrency: None,
+            default_timeout: None,
             inherit_colors_for_piped_tasks: true,
         }
     }
 }
 
+fn default_cache_max_age() -> u32 {
+    7
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheConfig {
+    #[serde(default)]
+    pub auto_clean: bool,
+
+    /// Max age of unused hashes and outputs, in days.
+    #[serde(default = "default_cache_max_age")]
+    pub max_age: u32,
+
+    /// Max total size of hashes and outputs, in megabytes.
+    pub max_size: Option<u32>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            auto_clean: false,
+            max_age: default_cache_max_age(),
+            max_size: None,
+        }
+    }
+}
+
+fn validate_remote_cache_host(value: &str) -> Result<(), ValidationError> {
+    validate_url("remoteCache.host", value)
+}
+
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteCacheConfig {
+    #[validate(custom = "validate_remote_cache_host")]
+    pub host: String,
+}
+
 /// Docs: https://moonrepo.dev/docs/config/workspace
 #[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize, Validate)]
 #[serde(rename_all = "camelCase")]
@@ -72,6 +134,10 @@ pub struct WorkspaceConfig {
     #[validate]
     pub action_runner: ActionRunnerConfig,
 
+    #[serde(default)]
+    #[validate]
+    pub cache: CacheConfig,
+
     #[serde(default)]
     #[validate]
     pub node: NodeConfig,
@@ -82,6 +148,9 @@ pub struct WorkspaceConfig {
     #[validate(custom = "validate_projects")]
     pub projects: ProjectsMap,
 
+    #[validate]
+    pub remote_cache: Option<RemoteCacheConfig>,
+
     #[serde(default)]
     #[validate]
     pub typescript: TypeScriptConfig,
@@ -250,8 +319,10 @@ mod tests {
                 config,
                 WorkspaceConfig {
                     action_runner: ActionRunnerConfig::default(),
+                    cache: CacheConfig::default(),
                     node: NodeConfig::default(),
                     projects: HashMap::new(),
+                    remote_cache: None,
                     typescript: TypeScriptConfig::default(),
                     vcs: VcsConfig::default(),
                     schema: String::new(),
@@ -282,11 +353,13 @@ node:
                     config,
                     WorkspaceConfig {
                         action_runner: ActionRunnerConfig::default(),
+                        cache: CacheConfig::default(),
                         node: NodeConfig {
                             package_manager: PackageManager::Yarn,
                             ..NodeConfig::default()
                         },
                         projects: HashMap::new(),
+                        remote_cache: None,
                         typescript: TypeScriptConfig::default(),
                         vcs: VcsConfig::default(),
                         schema: String::new(),
@@ -779,8 +852,10 @@ vcs:
                     config,
                     WorkspaceConfig {
                         action_runner: ActionRunnerConfig::default(),
+                        cache: CacheConfig::default(),
                         node: NodeConfig::default(),
                         projects: HashMap::new(),
+                        remote_cache: None,
                         typescript: TypeScriptConfig::default(),
                         vcs: VcsConfig {
                             manager: VcsManager::Svn,
@@ -853,4 +928,206 @@ vcs:
             });
         }
     }
+
+    mod remote_cache {
+        use super::*;
+
+        #[test]
+        fn loads_host() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(
+                    constants::CONFIG_WORKSPACE_FILENAME,
+                    r#"
+projects: {}
+remoteCache:
+    host: https://cache.example.com"#,
+                )?;
+
+                let config = super::load_jailed_config()?;
+
+                assert_eq!(
+                    config.remote_cache,
+                    Some(RemoteCacheConfig {
+                        host: String::from("https://cache.example.com"),
+                    })
+                );
+
+                Ok(())
+            });
+        }
+
+        #[test]
+        #[should_panic(
+            expected = "Invalid field <id>remoteCache.host</id>: Must be a valid URL, starting with http:// or https://."
+        )]
+        fn invalid_host() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(
+                    super::constants::CONFIG_WORKSPACE_FILENAME,
+                    r#"
+projects: {}
+remoteCache:
+    host: cache.example.com"#,
+                )?;
+
+                super::load_jailed_config()?;
+
+                Ok(())
+            });
+        }
+    }
+
+    mod cache {
+        use super::*;
+
+        #[test]
+        fn loads_defaults() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(constants::CONFIG_WORKSPACE_FILENAME, "projects: {}")?;
+
+                let config = super::load_jailed_config()?;
+
+                assert_eq!(
+                    config.cache,
+                    CacheConfig {
+                        auto_clean: false,
+                        max_age: 7,
+                        max_size: None,
+                    }
+                );
+
+                Ok(())
+            });
+        }
+
+        #[test]
+        fn loads_custom_values() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(
+                    constants::CONFIG_WORKSPACE_FILENAME,
+                    r#"
+projects: {}
+cache:
+    autoClean: true
+    maxAge: 30
+    maxSize: 1024"#,
+                )?;
+
+                let config = super::load_jailed_config()?;
+
+                assert_eq!(
+                    config.cache,
+                    CacheConfig {
+                        auto_clean: true,
+                        max_age: 30,
+                        max_size: Some(1024),
+                    }
+                );
+
+                Ok(())
+            });
+        }
+
+        #[test]
+        #[should_panic(
+            expected = "Invalid field <id>cache.maxAge</id>: Expected u32 type, received string \"abc\"."
+        )]
+        fn invalid_max_age_type() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(
+                    super::constants::CONFIG_WORKSPACE_FILENAME,
+                    r#"
+projects: {}
+cache:
+    maxAge: abc"#,
+                )?;
+
+                super::load_jailed_config()?;
+
+                Ok(())
+            });
+        }
+    }
+
+    mod action_runner {
+        use super::*;
+
+        #[test]
+        fn loads_defaults() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(constants::CONFIG_WORKSPACE_FILENAME, "projects: {}")?;
+
+                let config = super::load_jailed_config()?;
+
+                assert_eq!(
+                    config.action_runner,
+                    ActionRunnerConfig {
+                        concurrency: None,
+                        default_timeout: None,
+                        inherit_colors_for_piped_tasks: true,
+                    }
+                );
+
+                Ok(())
+            });
+        }
+
+        #[test]
+        fn loads_concurrency() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(
+                    constants::CONFIG_WORKSPACE_FILENAME,
+                    r#"
+projects: {}
+actionRunner:
+    concurrency: 4"#,
+                )?;
+
+                let config = super::load_jailed_config()?;
+
+                assert_eq!(config.action_runner.concurrency, Some(4));
+
+                Ok(())
+            });
+        }
+
+        #[test]
+        fn loads_default_timeout() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(
+                    constants::CONFIG_WORKSPACE_FILENAME,
+                    r#"
+projects: {}
+actionRunner:
+    defaultTimeout: 600"#,
+                )?;
+
+                let config = super::load_jailed_config()?;
+
+                assert_eq!(config.action_runner.default_timeout, Some(600));
+
+                Ok(())
+            });
+        }
+
+        #[test]
+        #[should_panic(
+            expected = "Invalid field <id>actionRunner.concurrency</id>: Must be greater than 0."
+        )]
+        fn invalid_zero_concurrency() {
+            figment::Jail::expect_with(|jail| {
+                jail.create_file(
+                    super::constants::CONFIG_WORKSPACE_FILENAME,
+                    r#"
+projects: {}
+actionRunner:
+    concurrency: 0"#,
+                )?;
+
+                super::load_jailed_config()?;
+
+                Ok(())
+            });
+        }
+    }
 }
@@ -15,6 +15,9 @@ pub enum MoonError {
     #[error("Failed to create a hard link from <path>{0}</path> to <path>{1}</path>.")]
     HardLink(PathBuf, PathBuf),
 
+    #[error("HTTP request to <url>{0}</url> failed: {1}")]
+    Http(String, String),
+
     #[error("Failed to parse <path>{0}</path>: {1}")]
     Json(PathBuf, #[source] JsonError),
 
@@ -36,6 +39,9 @@ pub enum MoonError {
     #[error("Process <shell>{0}</shell> failed with a <symbol>{1}</symbol> exit code.\n<muted>{2}</muted>")]
     ProcessNonZeroWithOutput(String, i32, String),
 
+    #[error("Process <shell>{0}</shell> timed out after <symbol>{1}</symbol> seconds.")]
+    ProcessTimeout(String, u64),
+
     #[error("{0}")]
     Unknown(#[source] IoError),
 }
@@ -4,10 +4,113 @@ use moon_project::{Project, Task};
 use moon_utils::path;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// A single difference between 2 hashers, as reported by `TargetHasher.diff()`.
+#[derive(Debug, PartialEq)]
+pub struct TargetHasherDiff {
+    /// Name of the field that changed, as it appears in the hash manifest.
+    pub field: String,
+
+    /// Key within a map field (file path, dependency name, etc).
+    pub key: Option<String>,
+
+    pub previous: Option<String>,
+
+    pub current: Option<String>,
+}
+
+impl fmt::Display for TargetHasherDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match &self.key {
+            Some(key) => format!("{}[{}]", self.field, key),
+            None => self.field.clone(),
+        };
+
+        match (&self.previous, &self.current) {
+            (Some(previous), Some(current)) => write!(f, "{}: {} -> {}", name, previous, current),
+            (None, Some(current)) => write!(f, "{}: added {}", name, current),
+            (Some(previous), None) => write!(f, "{}: removed {}", name, previous),
+            (None, None) => write!(f, "{}: changed", name),
+        }
+    }
+}
+
+fn diff_string(diffs: &mut Vec<TargetHasherDiff>, field: &str, previous: &str, current: &str) {
+    if previous != current {
+        diffs.push(TargetHasherDiff {
+            field: field.to_owned(),
+            key: None,
+            previous: Some(previous.to_owned()),
+            current: Some(current.to_owned()),
+        });
+    }
+}
+
+fn diff_vec(
+    diffs: &mut Vec<TargetHasherDiff>,
+    field: &str,
+    previous: &[String],
+    current: &[String],
+) {
+    if previous != current {
+        diffs.push(TargetHasherDiff {
+            field: field.to_owned(),
+            key: None,
+            previous: Some(format!("[{}]", previous.join(", "))),
+            current: Some(format!("[{}]", current.join(", "))),
+        });
+    }
+}
+
+fn diff_btree(
+    diffs: &mut Vec<TargetHasherDiff>,
+    field: &str,
+    previous: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+) {
+    let keys: BTreeSet<&String> = previous.keys().chain(current.keys()).collect();
+
+    for key in keys {
+        let previous_value = previous.get(key);
+        let current_value = current.get(key);
+
+        if previous_value != current_value {
+            diffs.push(TargetHasherDiff {
+                field: field.to_owned(),
+                key: Some(key.to_owned()),
+                previous: previous_value.cloned(),
+                current: current_value.cloned(),
+            });
+        }
+    }
+}
+
+// Values may be secrets (only their hashes are stored), so only report
+// which keys have changed, and not their previous or current values.
+fn diff_btree_keys(
+    diffs: &mut Vec<TargetHasherDiff>,
+    field: &str,
+    previous: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+) {
+    let keys: BTreeSet<&String> = previous.keys().chain(current.keys()).collect();
+
+    for key in keys {
+        if previous.get(key) != current.get(key) {
+            diffs.push(TargetHasherDiff {
+                field: field.to_owned(),
+                key: Some(key.to_owned()),
+                previous: None,
+                current: None,
+            });
+        }
+    }
+}
 
 #[derive(Default, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(default, rename_all = "camelCase")]
 pub struct TargetHasher {
     // Task `command`
     command: String,
@@ -24,6 +127,9 @@ pub struct TargetHasher {
     // Input files and globs mapped to a unique hash
     input_hashes: BTreeMap<String, String>,
 
+    // Input environment variables mapped to a SHA256 of their runtime value
+    input_vars: BTreeMap<String, String>,
+
     // Node.js version
     node_version: String,
 
@@ -59,6 +165,69 @@ impl TargetHasher {
         }
     }
 
+    /// Compare against a previous hasher (typically loaded from a hash manifest),
+    /// and return a list of every field that has changed.
+    pub fn diff(&self, previous: &TargetHasher) -> Vec<TargetHasherDiff> {
+        let mut diffs = vec![];
+
+        diff_string(&mut diffs, "version", &previous.version, &self.version);
+        diff_string(
+            &mut diffs,
+            "nodeVersion",
+            &previous.node_version,
+            &self.node_version,
+        );
+        diff_string(&mut diffs, "target", &previous.target, &self.target);
+        diff_string(&mut diffs, "command", &previous.command, &self.command);
+        diff_vec(&mut diffs, "args", &previous.args, &self.args);
+        diff_vec(&mut diffs, "deps", &previous.deps, &self.deps);
+        diff_btree(&mut diffs, "envVars", &previous.env_vars, &self.env_vars);
+        diff_btree(
+            &mut diffs,
+            "inputHashes",
+            &previous.input_hashes,
+            &self.input_hashes,
+        );
+        diff_btree_keys(
+            &mut diffs,
+            "inputVars",
+            &previous.input_vars,
+            &self.input_vars,
+        );
+        diff_vec(
+            &mut diffs,
+            "projectDeps",
+            &previous.project_deps,
+            &self.project_deps,
+        );
+        diff_btree(
+            &mut diffs,
+            "packageDependencies",
+            &previous.package_dependencies,
+            &self.package_dependencies,
+        );
+        diff_btree(
+            &mut diffs,
+            "packageDevDependencies",
+            &previous.package_dev_dependencies,
+            &self.package_dev_dependencies,
+        );
+        diff_btree(
+            &mut diffs,
+            "packagePeerDependencies",
+            &previous.package_peer_dependencies,
+            &self.package_peer_dependencies,
+        );
+        diff_btree(
+            &mut diffs,
+            "tsconfigCompilerOptions",
+            &previous.tsconfig_compiler_options,
+            &self.tsconfig_compiler_options,
+        );
+
+        diffs
+    }
+
     pub fn hash_args(&mut self, passthrough_args: &[String]) {
         if !passthrough_args.is_empty() {
             for arg in passthrough_args {
@@ -81,6 +250,17 @@ impl TargetHasher {
         }
     }
 
+    /// Hash a mapping of input environment variables to their runtime values.
+    /// Variables that are not set should be mapped to an empty string.
+    /// Values may be secrets, and the hasher is persisted to the cache (and shared
+    /// with the remote cache), so only a hash of each value is stored.
+    pub fn hash_input_vars(&mut self, vars: BTreeMap<String, String>) {
+        for (var, value) in vars {
+            self.input_vars
+                .insert(var, format!("{:x}", Sha256::digest(value.as_bytes())));
+        }
+    }
+
     /// Hash `package.json` dependencies as version changes should bust the cache.
     pub fn hash_package_json(&mut self, package: &PackageJson) {
         if let Some(deps) = &package.dependencies {
@@ -157,6 +337,7 @@ impl TargetHasher {
         hash_vec(&self.deps, &mut sha);
         hash_btree(&self.env_vars, &mut sha);
         hash_btree(&self.input_hashes, &mut sha);
+        hash_btree(&self.input_vars, &mut sha);
 
         // Deps
         hash_vec(&self.project_deps, &mut sha);
@@ -200,6 +381,139 @@ mod tests {
         assert_ne!(hasher1.to_hash(), hasher2.to_hash());
     }
 
+    mod diff {
+        use super::*;
+
+        #[test]
+        fn returns_nothing_if_same() {
+            let hasher1 = TargetHasher::new(String::from("0.0.0"));
+            let hasher2 = TargetHasher::new(String::from("0.0.0"));
+
+            assert_eq!(hasher2.diff(&hasher1), vec![]);
+        }
+
+        #[test]
+        fn returns_changed_strings_and_vecs() {
+            let hasher1 = TargetHasher::new(String::from("0.0.0"));
+            let mut hasher2 = TargetHasher::new(String::from("1.0.0"));
+            hasher2.hash_args(&["--foo".to_owned()]);
+
+            assert_eq!(
+                hasher2.diff(&hasher1),
+                vec![
+                    TargetHasherDiff {
+                        field: "nodeVersion".to_owned(),
+                        key: None,
+                        previous: Some("0.0.0".to_owned()),
+                        current: Some("1.0.0".to_owned()),
+                    },
+                    TargetHasherDiff {
+                        field: "args".to_owned(),
+                        key: None,
+                        previous: Some("[]".to_owned()),
+                        current: Some("[--foo]".to_owned()),
+                    }
+                ]
+            );
+        }
+
+        #[test]
+        fn returns_added_removed_and_changed_keys() {
+            let mut hasher1 = TargetHasher::new(String::from("0.0.0"));
+            hasher1.hash_inputs(BTreeMap::from([
+                ("a.js".to_owned(), "aaa".to_owned()),
+                ("b.js".to_owned(), "bbb".to_owned()),
+            ]));
+
+            let mut hasher2 = TargetHasher::new(String::from("0.0.0"));
+            hasher2.hash_inputs(BTreeMap::from([
+                ("b.js".to_owned(), "BBB".to_owned()),
+                ("c.js".to_owned(), "ccc".to_owned()),
+            ]));
+
+            let diffs = hasher2.diff(&hasher1);
+
+            assert_eq!(
+                diffs.iter().map(|d| d.to_string()).collect::<Vec<_>>(),
+                vec![
+                    "inputHashes[a.js]: removed aaa",
+                    "inputHashes[b.js]: bbb -> BBB",
+                    "inputHashes[c.js]: added ccc",
+                ]
+            );
+        }
+
+        #[test]
+        fn can_diff_against_a_deserialized_manifest() {
+            let mut hasher = TargetHasher::new(String::from("16.0.0"));
+            hasher.hash_args(&["--foo".to_owned()]);
+
+            let previous: TargetHasher =
+                serde_json::from_str(&serde_json::to_string(&hasher).unwrap()).unwrap();
+
+            assert_eq!(hasher.diff(&previous), vec![]);
+
+            // Missing fields should fallback to defaults
+            let previous: TargetHasher =
+                serde_json::from_str(r#"{"nodeVersion":"16.0.0"}"#).unwrap();
+
+            assert_eq!(hasher.diff(&previous).len(), 2);
+        }
+    }
+
+    mod input_vars {
+        use super::*;
+
+        #[test]
+        fn returns_diff_hash_for_diff_values() {
+            let mut hasher1 = TargetHasher::new(String::from("0.0.0"));
+            hasher1.hash_input_vars(BTreeMap::from([(
+                "NODE_ENV".to_owned(),
+                "development".to_owned(),
+            )]));
+
+            let mut hasher2 = TargetHasher::new(String::from("0.0.0"));
+            hasher2.hash_input_vars(BTreeMap::from([(
+                "NODE_ENV".to_owned(),
+                "production".to_owned(),
+            )]));
+
+            assert_ne!(hasher1.to_hash(), hasher2.to_hash());
+            assert_eq!(
+                hasher2
+                    .diff(&hasher1)
+                    .iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>(),
+                vec!["inputVars[NODE_ENV]: changed"]
+            );
+        }
+
+        #[test]
+        fn doesnt_store_raw_values() {
+            let mut hasher = TargetHasher::new(String::from("0.0.0"));
+            hasher.hash_input_vars(BTreeMap::from([(
+                "API_TOKEN".to_owned(),
+                "secret-token".to_owned(),
+            )]));
+
+            let manifest = serde_json::to_string(&hasher).unwrap();
+
+            assert!(manifest.contains("API_TOKEN"));
+            assert!(!manifest.contains("secret-token"));
+        }
+
+        #[test]
+        fn returns_same_hash_when_empty() {
+            let hasher1 = TargetHasher::new(String::from("0.0.0"));
+
+            let mut hasher2 = TargetHasher::new(String::from("0.0.0"));
+            hasher2.hash_input_vars(BTreeMap::new());
+
+            assert_eq!(hasher1.to_hash(), hasher2.to_hash());
+        }
+    }
+
     mod btreemap {
         use super::*;
 
@@ -1,3 +1,3 @@
 mod hasher;
 
-pub use hasher::TargetHasher;
+pub use hasher::{TargetHasher, TargetHasherDiff};
@@ -62,10 +62,17 @@ pub enum TargetError {
     )]
     NoProjectAllInTaskDeps(String),
 
-    #[error("Project dependencies scope (^:) is not supported in run contexts.")]
+    #[error("Wildcard tasks are not supported in task deps, for target <target>{0}</target>.")]
+    NoTaskWildcardInTaskDeps(String),
+
+    #[error(
+        "Project dependencies scope (^:) is only supported when ran from within a project's directory."
+    )]
     NoProjectDepsInRunContext,
 
-    #[error("Project self scope (~:) is not supported in run contexts.")]
+    #[error(
+        "Project self scope (~:) is only supported when ran from within a project's directory."
+    )]
     NoProjectSelfInRunContext,
 }
 
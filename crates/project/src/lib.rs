@@ -3,6 +3,7 @@ mod errors;
 mod file_group;
 mod helpers;
 mod project;
+mod project_filter;
 mod project_graph;
 mod target;
 mod task;
@@ -16,13 +17,14 @@ pub use helpers::*;
 pub use types::*;
 
 // Projects
-pub use moon_config::{ProjectID, ProjectType};
+pub use moon_config::{ProjectID, ProjectLanguage, ProjectType};
 pub use project::{Project, ProjectsMap};
+pub use project_filter::ProjectFilter;
 pub use project_graph::ProjectGraph;
 
 // Tasks & targets
 pub use moon_config::{TargetID, TaskID, TaskType};
-pub use target::{Target, TargetProject};
+pub use target::{Target, TargetProject, TargetTask};
 pub use task::{Task, TaskOptions};
 
 // Tokens
@@ -0,0 +1,118 @@
+use crate::project::Project;
+use moon_config::{ProjectConfig, ProjectLanguage, ProjectType};
+
+/// Filters projects by their configured language, type, and tags. A project matches
+/// a filter when it has any of the filter's values, and must match every filter.
+/// Empty filters match all projects.
+#[derive(Clone, Debug, Default)]
+pub struct ProjectFilter {
+    pub languages: Vec<ProjectLanguage>,
+
+    pub tags: Vec<String>,
+
+    pub types: Vec<ProjectType>,
+}
+
+impl ProjectFilter {
+    pub fn is_empty(&self) -> bool {
+        self.languages.is_empty() && self.tags.is_empty() && self.types.is_empty()
+    }
+
+    pub fn matches(&self, project: &Project) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+
+        // Projects without a config use the defaults
+        let default_config = ProjectConfig::default();
+        let config = project.config.as_ref().unwrap_or(&default_config);
+
+        (self.languages.is_empty() || self.languages.contains(&config.language))
+            && (self.types.is_empty() || self.types.contains(&config.type_of))
+            && (self.tags.is_empty() || self.tags.iter().any(|tag| config.tags.contains(tag)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use moon_utils::string_vec;
+
+    fn create_project(config: Option<ProjectConfig>) -> Project {
+        Project {
+            config,
+            id: String::from("app"),
+            ..Project::default()
+        }
+    }
+
+    #[test]
+    fn matches_all_when_empty() {
+        let filter = ProjectFilter::default();
+
+        assert!(filter.matches(&create_project(None)));
+        assert!(filter.matches(&create_project(Some(ProjectConfig {
+            type_of: ProjectType::Tool,
+            ..ProjectConfig::default()
+        }))));
+    }
+
+    #[test]
+    fn matches_any_value_of_a_filter() {
+        let filter = ProjectFilter {
+            types: vec![ProjectType::Application, ProjectType::Tool],
+            ..ProjectFilter::default()
+        };
+
+        assert!(filter.matches(&create_project(Some(ProjectConfig {
+            type_of: ProjectType::Tool,
+            ..ProjectConfig::default()
+        }))));
+        assert!(!filter.matches(&create_project(Some(ProjectConfig {
+            type_of: ProjectType::Library,
+            ..ProjectConfig::default()
+        }))));
+    }
+
+    #[test]
+    fn matches_every_filter() {
+        let filter = ProjectFilter {
+            languages: vec![ProjectLanguage::JavaScript],
+            tags: string_vec!["ui"],
+            ..ProjectFilter::default()
+        };
+
+        assert!(filter.matches(&create_project(Some(ProjectConfig {
+            language: ProjectLanguage::JavaScript,
+            tags: string_vec!["frontend", "ui"],
+            ..ProjectConfig::default()
+        }))));
+        assert!(!filter.matches(&create_project(Some(ProjectConfig {
+            language: ProjectLanguage::TypeScript,
+            tags: string_vec!["ui"],
+            ..ProjectConfig::default()
+        }))));
+        assert!(!filter.matches(&create_project(Some(ProjectConfig {
+            language: ProjectLanguage::JavaScript,
+            ..ProjectConfig::default()
+        }))));
+    }
+
+    #[test]
+    fn uses_defaults_without_config() {
+        let filter = ProjectFilter {
+            languages: vec![ProjectLanguage::TypeScript],
+            types: vec![ProjectType::Library],
+            ..ProjectFilter::default()
+        };
+
+        assert!(filter.matches(&create_project(None)));
+
+        let filter = ProjectFilter {
+            tags: string_vec!["ui"],
+            ..ProjectFilter::default()
+        };
+
+        assert!(!filter.matches(&create_project(None)));
+    }
+}
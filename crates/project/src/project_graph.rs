@@ -34,6 +34,7 @@ async fn load_projects_from_cache(
         return Ok(projects.clone());
     }
 
+    let _lock = engine.lock_projects_state().await?;
     let mut cache = engine.cache_projects_state().await?;
 
     // Return the values from the cache
@@ -159,6 +160,17 @@ impl ProjectGraph {
         Ok(graph.node_weight(index).unwrap().clone())
     }
 
+    /// Find the project that owns the provided file path, by matching it against
+    /// project roots. When projects are nested, the deepest root is preferred.
+    pub fn find_owning_project(&self, path: &Path) -> Option<ProjectID> {
+        self.projects_config
+            .iter()
+            .map(|(id, source)| (id, self.workspace_root.join(source)))
+            .filter(|(_, root)| path.starts_with(root))
+            .max_by_key(|(_, root)| root.components().count())
+            .map(|(id, _)| id.clone())
+    }
+
     /// Return a list of direct project IDs that the defined project depends on.
     pub fn get_dependencies_of(&self, project: &Project) -> Result<Vec<ProjectID>, ProjectError> {
         let indices = self.indices.read().expect(READ_ERROR);
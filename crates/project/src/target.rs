@@ -1,8 +1,10 @@
 use crate::errors::{ProjectError, TargetError};
 use moon_config::{ProjectID, TargetID, TaskID};
-use moon_utils::regex::TARGET_PATTERN;
+use moon_utils::glob::GlobSet;
+use moon_utils::regex::TARGET_WILDCARD_PATTERN;
 use std::cmp::Ordering;
 use std::fmt;
+use std::path::Path;
 
 #[derive(Clone, Debug, Eq, PartialEq, PartialOrd)]
 pub enum TargetProject {
@@ -23,20 +25,22 @@ impl fmt::Display for TargetProject {
     }
 }
 
-// #[derive(Debug, PartialEq)]
-// pub enum TargetTask {
-//     All,        // project:
-//     Id(TaskID), // project:id
-// }
-
-// impl fmt::Display for TargetTask {
-//     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-//         match self {
-//             TargetTask::All => write!(f, ""),
-//             TargetTask::Id(name) => write!(f, "{}", name),
-//         }
-//     }
-// }
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TargetTask {
+    All,          // project:*
+    Glob(String), // project:task-*
+    Id(TaskID),   // project:task
+}
+
+impl fmt::Display for TargetTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetTask::All => write!(f, "*"),
+            TargetTask::Glob(glob) => write!(f, "{}", glob),
+            TargetTask::Id(id) => write!(f, "{}", id),
+        }
+    }
+}
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Target {
@@ -46,7 +50,7 @@ pub struct Target {
 
     pub project_id: Option<String>,
 
-    pub task_id: String,
+    pub task: TargetTask,
 }
 
 impl Target {
@@ -55,7 +59,7 @@ impl Target {
             id: Target::format(project_id, task_id)?,
             project: TargetProject::Id(project_id.to_owned()),
             project_id: Some(project_id.to_owned()),
-            task_id: task_id.to_owned(),
+            task: TargetTask::Id(task_id.to_owned()),
         })
     }
 
@@ -64,11 +68,11 @@ impl Target {
     }
 
     pub fn parse(target_id: &str) -> Result<Target, ProjectError> {
-        if target_id == ":" {
+        if target_id == ":" || target_id == ":*" {
             return Err(ProjectError::Target(TargetError::TooWild));
         }
 
-        let matches = match TARGET_PATTERN.captures(target_id) {
+        let matches = match TARGET_WILDCARD_PATTERN.captures(target_id) {
             Some(result) => result,
             None => {
                 return Err(ProjectError::Target(TargetError::InvalidFormat(
@@ -92,21 +96,17 @@ impl Target {
             None => TargetProject::All,
         };
 
-        let task_id = matches.name("task").unwrap().as_str().to_owned();
-
-        // let task = match matches.name("task") {
-        //     Some(value) => match value.as_str() {
-        //         "" => TargetTask::All,
-        //         id => TargetTask::Id(id.to_owned()),
-        //     },
-        //     None => TargetTask::All,
-        // };
+        let task = match matches.name("task").unwrap().as_str() {
+            "*" => TargetTask::All,
+            id if id.contains(|c: char| c == '*' || c == '?') => TargetTask::Glob(id.to_owned()),
+            id => TargetTask::Id(id.to_owned()),
+        };
 
         Ok(Target {
             id: target_id.to_owned(),
             project,
             project_id,
-            task_id,
+            task,
         })
     }
 
@@ -123,12 +123,24 @@ impl Target {
             },
         };
 
-        // let task_id = match &self.task {
-        //     TargetTask::Id(id) => id,
-        //     _ => return Err(ProjectError::Target(TargetError::IdOnly(self.id.clone()))),
-        // };
+        let task_id = match &self.task {
+            TargetTask::Id(id) => id,
+            _ => return Err(ProjectError::Target(TargetError::IdOnly(self.id.clone()))),
+        };
 
-        Ok((project_id.clone(), self.task_id.clone()))
+        Ok((project_id.clone(), task_id.clone()))
+    }
+
+    /// Whether the provided task ID matches the target's task,
+    /// which may be a literal ID, a wildcard, or a glob.
+    pub fn matches_task(&self, task_id: &str) -> Result<bool, ProjectError> {
+        Ok(match &self.task {
+            TargetTask::All => true,
+            TargetTask::Glob(glob) => {
+                GlobSet::new(std::slice::from_ref(glob))?.matches(Path::new(task_id))?
+            }
+            TargetTask::Id(id) => id == task_id,
+        })
     }
 }
 
@@ -161,8 +173,7 @@ mod tests {
                 id: String::from("foo:build"),
                 project: TargetProject::Id("foo".to_owned()),
                 project_id: Some("foo".to_owned()),
-                task_id: "build".to_owned(),
-                // task: TargetTask::Id("build".to_owned())
+                task: TargetTask::Id("build".to_owned()),
             }
         );
     }
@@ -175,23 +186,23 @@ mod tests {
                 id: String::from("^:build"),
                 project: TargetProject::Deps,
                 project_id: None,
-                task_id: "build".to_owned(),
-                // task: TargetTask::Id("build".to_owned())
+                task: TargetTask::Id("build".to_owned()),
             }
         );
     }
 
-    // #[test]
-    // fn parse_deps_project_all_tasks() {
-    //     assert_eq!(
-    //         Target::parse("^:").unwrap(),
-    //         Target {
-    //             id: String::from("^:"),
-    //             project: TargetProject::Deps,
-    //             task: TargetTask::All,
-    //         }
-    //     );
-    // }
+    #[test]
+    fn parse_deps_project_all_tasks() {
+        assert_eq!(
+            Target::parse("^:*").unwrap(),
+            Target {
+                id: String::from("^:*"),
+                project: TargetProject::Deps,
+                project_id: None,
+                task: TargetTask::All,
+            }
+        );
+    }
 
     #[test]
     fn parse_self_project() {
@@ -201,23 +212,23 @@ mod tests {
                 id: String::from("~:build"),
                 project: TargetProject::Own,
                 project_id: None,
-                task_id: "build".to_owned(),
-                // task: TargetTask::Id("build".to_owned())
+                task: TargetTask::Id("build".to_owned()),
             }
         );
     }
 
-    // #[test]
-    // fn parse_self_project_all_tasks() {
-    //     assert_eq!(
-    //         Target::parse("~:").unwrap(),
-    //         Target {
-    //             id: String::from("~:"),
-    //             project: TargetProject::Own,
-    //             task: TargetTask::All,
-    //         }
-    //     );
-    // }
+    #[test]
+    fn parse_self_project_all_tasks() {
+        assert_eq!(
+            Target::parse("~:*").unwrap(),
+            Target {
+                id: String::from("~:*"),
+                project: TargetProject::Own,
+                project_id: None,
+                task: TargetTask::All,
+            }
+        );
+    }
 
     #[test]
     fn parse_all_projects() {
@@ -227,27 +238,79 @@ mod tests {
                 id: String::from(":build"),
                 project: TargetProject::All,
                 project_id: None,
-                task_id: "build".to_owned(),
-                // task: TargetTask::Id("build".to_owned())
+                task: TargetTask::Id("build".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_all_tasks() {
+        assert_eq!(
+            Target::parse("foo:*").unwrap(),
+            Target {
+                id: String::from("foo:*"),
+                project: TargetProject::Id("foo".to_owned()),
+                project_id: Some("foo".to_owned()),
+                task: TargetTask::All,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_task_globs() {
+        assert_eq!(
+            Target::parse("foo:lint-*").unwrap(),
+            Target {
+                id: String::from("foo:lint-*"),
+                project: TargetProject::Id("foo".to_owned()),
+                project_id: Some("foo".to_owned()),
+                task: TargetTask::Glob("lint-*".to_owned()),
             }
         );
+        assert_eq!(
+            Target::parse(":test-?").unwrap().task,
+            TargetTask::Glob("test-?".to_owned())
+        );
     }
 
-    // #[test]
-    // fn parse_all_tasks() {
-    //     assert_eq!(
-    //         Target::parse("foo:").unwrap(),
-    //         Target {
-    //             id: String::from("foo:"),
-    //             project: TargetProject::Id("foo".to_owned()),
-    //             task: TargetTask::All,
-    //         }
-    //     );
-    // }
+    #[test]
+    fn matches_tasks() {
+        let target = Target::parse("foo:lint-*").unwrap();
+
+        assert!(target.matches_task("lint-js").unwrap());
+        assert!(target.matches_task("lint-").unwrap());
+        assert!(!target.matches_task("lint").unwrap());
+        assert!(!target.matches_task("test-lint-js").unwrap());
+
+        assert!(Target::parse("foo:*")
+            .unwrap()
+            .matches_task("build")
+            .unwrap());
+        assert!(Target::parse("foo:build")
+            .unwrap()
+            .matches_task("build")
+            .unwrap());
+        assert!(!Target::parse("foo:build")
+            .unwrap()
+            .matches_task("lint")
+            .unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "Target(IdOnly(\"foo:*\"))")]
+    fn ids_requires_task_id() {
+        Target::parse("foo:*").unwrap().ids().unwrap();
+    }
 
     #[test]
     #[should_panic(expected = "Target(TooWild)")]
     fn parse_too_wild() {
         Target::parse(":").unwrap();
     }
+
+    #[test]
+    #[should_panic(expected = "Target(TooWild)")]
+    fn parse_too_wild_all_tasks() {
+        Target::parse(":*").unwrap();
+    }
 }
@@ -1,11 +1,13 @@
 use crate::errors::{ProjectError, TargetError};
-use crate::target::{Target, TargetProject};
+use crate::target::{Target, TargetProject, TargetTask};
 use crate::token::TokenResolver;
 use crate::types::{EnvVars, ExpandedFiles, TouchedFilePaths};
 use moon_config::{
-    FilePath, FilePathOrGlob, TargetID, TaskConfig, TaskMergeStrategy, TaskOptionsConfig, TaskType,
+    FilePath, FilePathOrGlob, TargetID, TaskConfig, TaskMergeStrategy, TaskOptionsConfig,
+    TaskOutputStyle, TaskType,
 };
 use moon_logger::{color, debug, map_list, trace, Logable};
+use moon_utils::regex::matches_env_var;
 use moon_utils::{glob, path, string_vec};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
@@ -13,6 +15,8 @@ use std::collections::HashSet;
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TaskOptions {
+    pub cache: bool,
+
     pub merge_args: TaskMergeStrategy,
 
     pub merge_deps: TaskMergeStrategy,
@@ -23,15 +27,27 @@ pub struct TaskOptions {
 
     pub merge_outputs: TaskMergeStrategy,
 
+    pub mutex: Vec<String>,
+
+    pub output_style: Option<TaskOutputStyle>,
+
+    pub persistent: bool,
+
     pub retry_count: u8,
 
     pub run_in_ci: bool,
 
     pub run_from_workspace_root: bool,
+
+    pub timeout: Option<u64>,
 }
 
 impl TaskOptions {
     pub fn merge(&mut self, config: &TaskOptionsConfig) {
+        if let Some(cache) = &config.cache {
+            self.cache = *cache;
+        }
+
         if let Some(merge_args) = &config.merge_args {
             self.merge_args = merge_args.clone();
         }
@@ -52,6 +68,18 @@ impl TaskOptions {
             self.merge_outputs = merge_outputs.clone();
         }
 
+        if let Some(mutex) = &config.mutex {
+            self.mutex = mutex.clone();
+        }
+
+        if let Some(output_style) = &config.output_style {
+            self.output_style = Some(output_style.clone());
+        }
+
+        if let Some(persistent) = &config.persistent {
+            self.persistent = *persistent;
+        }
+
         if let Some(retry_count) = &config.retry_count {
             self.retry_count = *retry_count;
         }
@@ -63,6 +91,10 @@ impl TaskOptions {
         if let Some(run_from_workspace_root) = &config.run_from_workspace_root {
             self.run_from_workspace_root = *run_from_workspace_root;
         }
+
+        if let Some(timeout) = &config.timeout {
+            self.timeout = Some(*timeout);
+        }
     }
 }
 
@@ -83,6 +115,8 @@ pub struct Task {
 
     pub input_paths: ExpandedFiles,
 
+    pub input_vars: HashSet<String>,
+
     #[serde(skip)]
     pub log_target: String,
 
@@ -109,7 +143,8 @@ impl Task {
         let cloned_config = config.clone();
         let cloned_options = cloned_config.options;
         let command = cloned_config.command.unwrap_or_default();
-        let is_long_running = command == "serve" || command == "start";
+        let is_persistent = cloned_options.persistent.unwrap_or_default();
+        let is_long_running = is_persistent || command == "serve" || command == "start";
         let log_target = format!("moon:project:{}", target);
 
         let task = Task {
@@ -120,16 +155,22 @@ impl Task {
             inputs: cloned_config.inputs.unwrap_or_else(|| string_vec!["**/*"]),
             input_globs: vec![],
             input_paths: HashSet::new(),
+            input_vars: HashSet::new(),
             log_target,
             options: TaskOptions {
+                cache: cloned_options.cache.unwrap_or(true),
                 merge_args: cloned_options.merge_args.unwrap_or_default(),
                 merge_deps: cloned_options.merge_deps.unwrap_or_default(),
                 merge_env: cloned_options.merge_env.unwrap_or_default(),
                 merge_inputs: cloned_options.merge_inputs.unwrap_or_default(),
                 merge_outputs: cloned_options.merge_outputs.unwrap_or_default(),
+                mutex: cloned_options.mutex.unwrap_or_default(),
+                output_style: cloned_options.output_style,
+                persistent: is_persistent,
                 retry_count: cloned_options.retry_count.unwrap_or_default(),
                 run_in_ci: cloned_options.run_in_ci.unwrap_or(!is_long_running),
                 run_from_workspace_root: cloned_options.run_from_workspace_root.unwrap_or_default(),
+                timeout: cloned_options.timeout,
             },
             outputs: cloned_config.outputs.unwrap_or_default(),
             output_paths: HashSet::new(),
@@ -219,16 +260,25 @@ impl Task {
         for dep in &self.deps {
             let target = Target::parse(dep)?;
 
+            let task_id = match &target.task {
+                TargetTask::Id(id) => id,
+                _ => {
+                    return Err(ProjectError::Target(TargetError::NoTaskWildcardInTaskDeps(
+                        target.id.clone(),
+                    )))
+                }
+            };
+
             match &target.project {
                 // ^:task
                 TargetProject::Deps => {
                     for project_id in depends_on {
-                        push_dep(Target::format(project_id, &target.task_id)?);
+                        push_dep(Target::format(project_id, task_id)?);
                     }
                 }
                 // ~:task
                 TargetProject::Own => {
-                    push_dep(Target::format(owner_id, &target.task_id)?);
+                    push_dep(Target::format(owner_id, task_id)?);
                 }
                 // project:task
                 TargetProject::Id(_) => {
@@ -245,13 +295,25 @@ impl Task {
         Ok(())
     }
 
-    /// Expand the inputs list to a set of absolute file paths, while resolving tokens.
+    /// Expand the inputs list to a set of absolute file paths and environment variables,
+    /// while resolving tokens.
     pub fn expand_inputs(&mut self, token_resolver: TokenResolver) -> Result<(), ProjectError> {
         if self.inputs.is_empty() {
             return Ok(());
         }
 
-        for input in &token_resolver.resolve(&self.inputs, None)? {
+        // Environment variables are not files, so extract them before resolving
+        let mut inputs = vec![];
+
+        for input in &self.inputs {
+            if matches_env_var(input) {
+                self.input_vars.insert(input[1..].to_owned());
+            } else {
+                inputs.push(input.clone());
+            }
+        }
+
+        for input in &token_resolver.resolve(&inputs, None)? {
             // We cant canonicalize here as these inputs may not exist!
             if glob::is_path_glob(input) {
                 self.input_globs.push(glob::normalize(input)?);
@@ -329,6 +391,11 @@ impl Task {
         Ok(false)
     }
 
+    /// Persistent tasks never complete successfully, so are never cached.
+    pub fn is_cacheable(&self) -> bool {
+        self.options.cache && !self.options.persistent
+    }
+
     pub fn merge(&mut self, config: &TaskConfig) {
         // Merge options first incase the merge strategy has changed
         self.options.merge(&config.options);
@@ -444,6 +511,192 @@ mod tests {
         .unwrap();
     }
 
+    mod options {
+        use super::*;
+        use crate::task::Task;
+        use moon_config::TaskOptionsConfig;
+
+        fn create_options_config(cache: Option<bool>) -> TaskOptionsConfig {
+            TaskOptionsConfig {
+                cache,
+                merge_args: None,
+                merge_deps: None,
+                merge_env: None,
+                merge_inputs: None,
+                merge_outputs: None,
+                mutex: None,
+                output_style: None,
+                persistent: None,
+                retry_count: None,
+                run_in_ci: None,
+                run_from_workspace_root: None,
+                timeout: None,
+            }
+        }
+
+        #[test]
+        fn caches_by_default() {
+            let task = Task::from_config("project:task".to_owned(), &TaskConfig::default());
+
+            assert!(task.options.cache);
+        }
+
+        #[test]
+        fn can_disable_cache() {
+            let task = Task::from_config(
+                "project:task".to_owned(),
+                &TaskConfig {
+                    options: create_options_config(Some(false)),
+                    ..TaskConfig::default()
+                },
+            );
+
+            assert!(!task.options.cache);
+        }
+
+        #[test]
+        fn merges_cache_option() {
+            let mut task = Task::from_config("project:task".to_owned(), &TaskConfig::default());
+
+            task.options.merge(&create_options_config(Some(false)));
+
+            assert!(!task.options.cache);
+
+            // Unset fields should not override
+            task.options.merge(&create_options_config(None));
+
+            assert!(!task.options.cache);
+        }
+
+        #[test]
+        fn persistent_tasks_are_not_cacheable() {
+            let task = Task::from_config(
+                "project:task".to_owned(),
+                &TaskConfig {
+                    options: TaskOptionsConfig {
+                        persistent: Some(true),
+                        ..create_options_config(Some(true))
+                    },
+                    ..TaskConfig::default()
+                },
+            );
+
+            assert!(task.options.persistent);
+            assert!(!task.options.run_in_ci);
+            assert!(!task.is_cacheable());
+        }
+
+        #[test]
+        fn merges_persistent_option() {
+            let mut task = Task::from_config("project:task".to_owned(), &TaskConfig::default());
+
+            assert!(!task.options.persistent);
+            assert!(task.is_cacheable());
+
+            task.options.merge(&TaskOptionsConfig {
+                persistent: Some(true),
+                ..create_options_config(None)
+            });
+
+            assert!(task.options.persistent);
+
+            // Unset fields should not override
+            task.options.merge(&create_options_config(None));
+
+            assert!(task.options.persistent);
+        }
+
+        #[test]
+        fn merges_mutex_option() {
+            let mut task = Task::from_config("project:task".to_owned(), &TaskConfig::default());
+
+            assert!(task.options.mutex.is_empty());
+
+            task.options.merge(&TaskOptionsConfig {
+                mutex: Some(string_vec!["db", "port-3000"]),
+                ..create_options_config(None)
+            });
+
+            assert_eq!(task.options.mutex, string_vec!["db", "port-3000"]);
+
+            // Unset fields should not override
+            task.options.merge(&create_options_config(None));
+
+            assert_eq!(task.options.mutex, string_vec!["db", "port-3000"]);
+        }
+
+        #[test]
+        fn has_no_timeout_by_default() {
+            let task = Task::from_config("project:task".to_owned(), &TaskConfig::default());
+
+            assert_eq!(task.options.timeout, None);
+        }
+
+        #[test]
+        fn merges_timeout_option() {
+            let mut task = Task::from_config("project:task".to_owned(), &TaskConfig::default());
+
+            task.options.merge(&TaskOptionsConfig {
+                timeout: Some(60),
+                ..create_options_config(None)
+            });
+
+            assert_eq!(task.options.timeout, Some(60));
+
+            // Unset fields should not override
+            task.options.merge(&create_options_config(None));
+
+            assert_eq!(task.options.timeout, Some(60));
+        }
+
+        #[test]
+        fn merges_output_style_option() {
+            let mut task = Task::from_config("project:task".to_owned(), &TaskConfig::default());
+
+            assert_eq!(task.options.output_style, None);
+
+            task.options.merge(&TaskOptionsConfig {
+                output_style: Some(TaskOutputStyle::Prefix),
+                ..create_options_config(None)
+            });
+
+            assert_eq!(task.options.output_style, Some(TaskOutputStyle::Prefix));
+
+            // Unset fields should not override
+            task.options.merge(&create_options_config(None));
+
+            assert_eq!(task.options.output_style, Some(TaskOutputStyle::Prefix));
+        }
+    }
+
+    mod expand_inputs {
+        use super::*;
+
+        #[test]
+        fn extracts_env_vars() {
+            let workspace_root = get_fixtures_dir("base");
+            let project_root = workspace_root.join("files-and-dirs");
+            let task = create_expanded_task(
+                &workspace_root,
+                &project_root,
+                Some(TaskConfig {
+                    inputs: Some(string_vec!["$NODE_ENV", "file.ts", "$API_URL"]),
+                    ..TaskConfig::default()
+                }),
+            )
+            .unwrap();
+
+            assert_eq!(
+                task.input_vars,
+                HashSet::from(["NODE_ENV".to_owned(), "API_URL".to_owned()])
+            );
+            assert_eq!(
+                task.input_paths,
+                HashSet::from([project_root.join("file.ts")])
+            );
+        }
+    }
+
     mod is_affected {
         use super::*;
 
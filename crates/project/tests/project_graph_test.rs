@@ -86,6 +86,52 @@ mod get_dependents_of {
     }
 }
 
+mod find_owning_project {
+    use super::*;
+
+    #[tokio::test]
+    async fn returns_deepest_project() {
+        let workspace_root = get_fixtures_dir("projects");
+        let graph = ProjectGraph::create(
+            &workspace_root,
+            GlobalProjectConfig::default(),
+            &HashMap::from([
+                ("root".to_owned(), ".".to_owned()),
+                ("foo".to_owned(), "deps/foo".to_owned()),
+                ("bar".to_owned(), "deps/bar".to_owned()),
+            ]),
+            &CacheEngine::create(&workspace_root).await.unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            graph.find_owning_project(&workspace_root.join("deps/foo")),
+            Some("foo".to_owned())
+        );
+        assert_eq!(
+            graph.find_owning_project(&workspace_root.join("deps/bar/src/index.ts")),
+            Some("bar".to_owned())
+        );
+        assert_eq!(
+            graph.find_owning_project(&workspace_root.join("deps")),
+            Some("root".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_none_outside_projects() {
+        let graph = get_dependencies_graph().await;
+        let workspace_root = get_fixtures_dir("project-graph/dependencies");
+
+        assert_eq!(graph.find_owning_project(&workspace_root), None);
+        assert_eq!(
+            graph.find_owning_project(&workspace_root.join("unknown/file.js")),
+            None
+        );
+    }
+}
+
 mod to_dot {
     use super::*;
 
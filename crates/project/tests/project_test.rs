@@ -146,6 +146,7 @@ fn advanced_config() {
                     maintainers: string_vec!["Bruce Wayne"],
                     channel: String::from("#batcave"),
                 }),
+                tags: string_vec!["frontend", "ui"],
                 type_of: ProjectType::Application,
                 ..ProjectConfig::default()
             }),
@@ -236,40 +237,55 @@ mod tasks {
 
     fn mock_merged_task_options_config(strategy: TaskMergeStrategy) -> TaskOptionsConfig {
         TaskOptionsConfig {
+            cache: None,
             merge_args: Some(strategy.clone()),
             merge_deps: Some(strategy.clone()),
             merge_env: Some(strategy.clone()),
             merge_inputs: Some(strategy.clone()),
             merge_outputs: Some(strategy),
+            mutex: None,
+            output_style: None,
+            persistent: None,
             retry_count: Some(1),
             run_in_ci: Some(true),
             run_from_workspace_root: None,
+            timeout: None,
         }
     }
 
     fn mock_local_task_options_config(strategy: TaskMergeStrategy) -> TaskOptionsConfig {
         TaskOptionsConfig {
+            cache: None,
             merge_args: Some(strategy.clone()),
             merge_deps: Some(strategy.clone()),
             merge_env: Some(strategy.clone()),
             merge_inputs: Some(strategy.clone()),
             merge_outputs: Some(strategy),
+            mutex: None,
+            output_style: None,
+            persistent: None,
             retry_count: None,
             run_in_ci: None,
             run_from_workspace_root: None,
+            timeout: None,
         }
     }
 
     fn stub_global_task_options_config() -> TaskOptionsConfig {
         TaskOptionsConfig {
+            cache: None,
             merge_args: None,
             merge_deps: None,
             merge_env: None,
             merge_inputs: None,
             merge_outputs: None,
+            mutex: None,
+            output_style: None,
+            persistent: None,
             retry_count: Some(1),
             run_in_ci: Some(true),
             run_from_workspace_root: None,
+            timeout: None,
         }
     }
 
@@ -680,14 +696,19 @@ mod tasks {
                             inputs: Some(string_vec!["b.*"]),
                             outputs: Some(string_vec!["b.ts"]),
                             options: TaskOptionsConfig {
+                                cache: None,
                                 merge_args: Some(TaskMergeStrategy::Append),
                                 merge_deps: Some(TaskMergeStrategy::Prepend),
                                 merge_env: Some(TaskMergeStrategy::Replace),
                                 merge_inputs: Some(TaskMergeStrategy::Replace),
                                 merge_outputs: Some(TaskMergeStrategy::Append),
+                                mutex: None,
+                                output_style: None,
+                                persistent: None,
                                 retry_count: None,
                                 run_in_ci: None,
                                 run_from_workspace_root: None,
+                                timeout: None,
                             },
                             type_of: TaskType::Node,
                         }
@@ -709,14 +730,19 @@ mod tasks {
                             inputs: Some(string_vec!["b.*"]),
                             outputs: Some(string_vec!["a.ts", "b.ts"]),
                             options: TaskOptionsConfig {
+                                cache: None,
                                 merge_args: Some(TaskMergeStrategy::Append),
                                 merge_deps: Some(TaskMergeStrategy::Prepend),
                                 merge_env: Some(TaskMergeStrategy::Replace),
                                 merge_inputs: Some(TaskMergeStrategy::Replace),
                                 merge_outputs: Some(TaskMergeStrategy::Append),
+                                mutex: None,
+                                output_style: None,
+                                persistent: None,
                                 retry_count: Some(1),
                                 run_in_ci: Some(true),
                                 run_from_workspace_root: None,
+                                timeout: None,
                             },
                             type_of: TaskType::Node,
                         },
@@ -809,6 +835,18 @@ mod tasks {
             )
             .unwrap();
         }
+
+        #[test]
+        #[should_panic(expected = "Invalid field <id>deps[0]</id>: Must be a valid target format.")]
+        fn errors_for_wildcard_tasks() {
+            Project::new(
+                "id",
+                "wildcard",
+                &get_fixtures_dir("task-deps"),
+                &mock_global_project_config(),
+            )
+            .unwrap();
+        }
     }
 
     mod tokens {
@@ -37,6 +37,11 @@ pub fn replace_style_tokens(value: &str) -> String {
     }))
 }
 
+/// Remove style tokens while keeping their contents, for writing to plain text files.
+pub fn strip_style_tokens(value: &str) -> String {
+    String::from(STYLE_TOKEN.replace_all(value, "$2"))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -72,4 +77,16 @@ mod test {
             );
         }
     }
+
+    mod strip_style_tokens {
+        use super::*;
+
+        #[test]
+        fn removes_tokens() {
+            assert_eq!(
+                strip_style_tokens("<muted>Before</muted> <id>inner</id> <symbol>after</symbol>"),
+                "Before inner after"
+            );
+        }
+    }
 }
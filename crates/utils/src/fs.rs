@@ -24,6 +24,40 @@ pub fn clean_json(json: String) -> Result<String, MoonError> {
     Ok(String::from(stripped))
 }
 
+pub async fn copy_file(from: &Path, to: &Path) -> Result<(), MoonError> {
+    if let Some(to_dir) = to.parent() {
+        create_dir_all(to_dir).await?;
+    }
+
+    fs::copy(from, to)
+        .await
+        .map_err(|e| map_io_to_fs_error(e, from.to_path_buf()))?;
+
+    Ok(())
+}
+
+#[async_recursion]
+pub async fn copy_dir_all(from_root: &Path, from: &Path, to_root: &Path) -> Result<(), MoonError> {
+    let entries = read_dir(from).await?;
+    let mut dirs = vec![];
+
+    for entry in entries {
+        let path = entry.path();
+
+        if path.is_file() {
+            copy_file(&path, &to_root.join(path.strip_prefix(from_root).unwrap())).await?;
+        } else {
+            dirs.push(path);
+        }
+    }
+
+    for dir in dirs {
+        copy_dir_all(from_root, &dir, to_root).await?;
+    }
+
+    Ok(())
+}
+
 pub async fn create_dir_all(path: &Path) -> Result<(), MoonError> {
     if !path.exists() {
         fs::create_dir_all(&path)
@@ -131,6 +165,14 @@ pub async fn read_dir_all(path: &Path) -> Result<Vec<fs::DirEntry>, MoonError> {
     Ok(results)
 }
 
+pub async fn read(path: &Path) -> Result<Vec<u8>, MoonError> {
+    let contents = fs::read(path)
+        .await
+        .map_err(|e| map_io_to_fs_error(e, path.to_path_buf()))?;
+
+    Ok(contents)
+}
+
 pub async fn read_json<T>(path: &Path) -> Result<T, MoonError>
 where
     T: DeserializeOwned,
@@ -171,6 +213,14 @@ pub async fn remove_dir_all(path: &Path) -> Result<(), MoonError> {
     Ok(())
 }
 
+pub async fn rename(from: &Path, to: &Path) -> Result<(), MoonError> {
+    fs::rename(from, to)
+        .await
+        .map_err(|e| map_io_to_fs_error(e, from.to_path_buf()))?;
+
+    Ok(())
+}
+
 pub async fn write(path: &Path, data: impl AsRef<[u8]>) -> Result<(), MoonError> {
     fs::write(path, data)
         .await
@@ -1,13 +1,17 @@
 use crate::path;
+use lazy_static::lazy_static;
 use moon_error::{map_io_to_process_error, MoonError};
 use moon_logger::{color, logging_enabled, trace};
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::ffi::OsStr;
-use std::path::Path;
-use std::sync::{Arc, RwLock};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::Command as TokioCommand;
-use tokio::task;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command as TokioCommand};
+use tokio::{task, time};
 
 pub use std::process::{ExitStatus, Output, Stdio};
 
@@ -34,6 +38,122 @@ pub fn output_to_trimmed_string(data: &[u8]) -> String {
     output_to_string(data).trim().to_owned()
 }
 
+/// Signals that can be forwarded to running child processes.
+#[derive(Clone, Copy, Debug)]
+pub enum ProcessSignal {
+    Interrupt,
+    Kill,
+    Terminate,
+}
+
+lazy_static! {
+    // Child processes that are currently running, mapped to whether
+    // they were spawned in their own process group
+    static ref RUNNING_PROCESSES: Mutex<HashMap<u32, bool>> = Mutex::new(HashMap::new());
+}
+
+// Tracks a child process as running, until dropped.
+struct RunningProcess {
+    pid: Option<u32>,
+    own_group: bool,
+}
+
+impl RunningProcess {
+    fn track(child: &Child) -> Self {
+        let pid = child.id();
+        let own_group = pid.map(is_process_group_leader).unwrap_or(false);
+
+        if let Some(pid) = pid {
+            RUNNING_PROCESSES.lock().unwrap().insert(pid, own_group);
+        }
+
+        RunningProcess { pid, own_group }
+    }
+}
+
+impl Drop for RunningProcess {
+    fn drop(&mut self) {
+        if let Some(pid) = self.pid {
+            RUNNING_PROCESSES.lock().unwrap().remove(&pid);
+        }
+    }
+}
+
+// A child spawned in its own process group (see `Command.new_process_group`) is the leader of it.
+#[cfg(unix)]
+fn is_process_group_leader(pid: u32) -> bool {
+    unsafe { libc::getpgid(pid as libc::pid_t) == pid as libc::pid_t }
+}
+
+#[cfg(windows)]
+fn is_process_group_leader(_pid: u32) -> bool {
+    false
+}
+
+// When the child is spawned in its own process group, we signal
+// the group instead, which includes all of its descendants.
+#[cfg(unix)]
+fn send_signal(pid: u32, own_group: bool, signal: ProcessSignal) {
+    let sig = match signal {
+        ProcessSignal::Interrupt => libc::SIGINT,
+        ProcessSignal::Kill => libc::SIGKILL,
+        ProcessSignal::Terminate => libc::SIGTERM,
+    };
+
+    let id = if own_group {
+        -(pid as libc::pid_t)
+    } else {
+        pid as libc::pid_t
+    };
+
+    unsafe {
+        libc::kill(id, sig);
+    }
+}
+
+// Windows doesn't have signals, and Ctrl+C is already delivered to every
+// process attached to the console, so the best we can do is kill the tree.
+#[cfg(windows)]
+fn send_signal(pid: u32, _own_group: bool, signal: ProcessSignal) {
+    if matches!(signal, ProcessSignal::Kill) {
+        let _ = std::process::Command::new("taskkill")
+            .args(["/F", "/T", "/PID", &pid.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// Forward a signal to all running child processes. Children within our own
+/// process group have already received an interrupt from the terminal,
+/// so they are not interrupted a second time.
+pub fn signal_running_processes(signal: ProcessSignal) {
+    for (pid, own_group) in RUNNING_PROCESSES.lock().unwrap().iter() {
+        if matches!(signal, ProcessSignal::Interrupt) && !own_group {
+            continue;
+        }
+
+        trace!(
+            target: "moon:utils",
+            "Sending {:?} signal to process {}",
+            signal,
+            pid
+        );
+
+        send_signal(*pid, *own_group, signal);
+    }
+}
+
+async fn read_to_end<T: AsyncRead + Unpin>(pipe: Option<T>) -> io::Result<Vec<u8>> {
+    let mut buffer = vec![];
+
+    if let Some(mut pipe) = pipe {
+        pipe.read_to_end(&mut buffer).await?;
+    }
+
+    Ok(buffer)
+}
+
 pub struct Command {
     bin: String,
 
@@ -41,6 +161,12 @@ pub struct Command {
 
     /// Convert non-zero exits to errors.
     error: bool,
+
+    /// Run the child in its own process group, unless it reads from a terminal.
+    new_group: bool,
+
+    /// Kill the process (and its descendants) if it runs longer than this.
+    timeout: Option<Duration>,
 }
 
 // This is rather annoying that we have to re-implement all these methods,
@@ -70,6 +196,8 @@ impl Command {
             bin: bin_name,
             cmd,
             error: true,
+            new_group: false,
+            timeout: None,
         }
     }
 
@@ -111,14 +239,52 @@ impl Command {
         self
     }
 
+    pub fn get_args(&self) -> Vec<String> {
+        self.cmd
+            .as_std()
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    pub fn get_bin_name(&self) -> &str {
+        &self.bin
+    }
+
+    pub fn get_cwd(&self) -> Option<PathBuf> {
+        self.cmd.as_std().get_current_dir().map(PathBuf::from)
+    }
+
+    /// Return all environment variables that were explicitly set on this command,
+    /// excluding those inherited from the parent process.
+    pub fn get_envs(&self) -> BTreeMap<String, String> {
+        self.cmd
+            .as_std()
+            .get_envs()
+            .filter_map(|(key, value)| {
+                value.map(|value| {
+                    (
+                        key.to_string_lossy().to_string(),
+                        value.to_string_lossy().to_string(),
+                    )
+                })
+            })
+            .collect()
+    }
+
     pub async fn exec_capture_output(&mut self) -> Result<Output, MoonError> {
         self.log_command_info(None);
 
-        let output = self.cmd.output();
-        let output = output
-            .await
+        let child = self
+            .cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
             .map_err(|e| map_io_to_process_error(e, &self.bin))?;
 
+        let output = self.wait_for_output(child, None).await?;
+
         self.handle_nonzero_status(&output)?;
 
         Ok(output)
@@ -130,7 +296,7 @@ impl Command {
     ) -> Result<Output, MoonError> {
         self.log_command_info(Some(input));
 
-        let mut child = self
+        let child = self
             .cmd
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -138,14 +304,7 @@ impl Command {
             .spawn()
             .map_err(|e| map_io_to_process_error(e, &self.bin))?;
 
-        let mut stdin = child.stdin.take().unwrap();
-        stdin.write_all(input.as_bytes()).await.unwrap();
-        drop(stdin);
-
-        let output = child
-            .wait_with_output()
-            .await
-            .map_err(|e| map_io_to_process_error(e, &self.bin))?;
+        let output = self.wait_for_output(child, Some(input)).await?;
 
         self.handle_nonzero_status(&output)?;
 
@@ -155,10 +314,14 @@ impl Command {
     pub async fn exec_stream_output(&mut self) -> Result<ExitStatus, MoonError> {
         self.log_command_info(None);
 
-        let status = self
+        let mut child = self
             .cmd
             .spawn()
-            .map_err(|e| map_io_to_process_error(e, &self.bin))?
+            .map_err(|e| map_io_to_process_error(e, &self.bin))?;
+
+        let _process = RunningProcess::track(&child);
+
+        let status = child
             .wait()
             .await
             .map_err(|e| map_io_to_process_error(e, &self.bin))?;
@@ -174,6 +337,23 @@ impl Command {
     }
 
     pub async fn exec_stream_and_capture_output(&mut self) -> Result<Output, MoonError> {
+        self.stream_and_capture_output(None).await
+    }
+
+    /// Like `exec_stream_and_capture_output`, but every streamed line is prefixed,
+    /// so that the output of processes running in parallel can be told apart.
+    pub async fn exec_stream_and_capture_output_with_prefix(
+        &mut self,
+        prefix: &str,
+    ) -> Result<Output, MoonError> {
+        self.stream_and_capture_output(Some(prefix.to_owned()))
+            .await
+    }
+
+    async fn stream_and_capture_output(
+        &mut self,
+        prefix: Option<String>,
+    ) -> Result<Output, MoonError> {
         self.log_command_info(None);
 
         let mut child = self
@@ -195,13 +375,19 @@ impl Command {
         let captured_stdout = Arc::new(RwLock::new(vec![]));
         let captured_stderr_clone = Arc::clone(&captured_stderr);
         let captured_stdout_clone = Arc::clone(&captured_stdout);
+        let stderr_prefix = prefix.clone();
+        let stdout_prefix = prefix;
 
         task::spawn(async move {
             let mut lines = stderr.lines();
             let mut captured_lines = vec![];
 
             while let Some(line) = lines.next_line().await.unwrap() {
-                eprintln!("{}", line);
+                match &stderr_prefix {
+                    Some(prefix) => eprintln!("{} {}", prefix, line),
+                    None => eprintln!("{}", line),
+                };
+
                 captured_lines.push(line);
             }
 
@@ -216,7 +402,11 @@ impl Command {
             let mut captured_lines = vec![];
 
             while let Some(line) = lines.next_line().await.unwrap() {
-                println!("{}", line);
+                match &stdout_prefix {
+                    Some(prefix) => println!("{} {}", prefix, line),
+                    None => println!("{}", line),
+                };
+
                 captured_lines.push(line);
             }
 
@@ -227,10 +417,7 @@ impl Command {
         });
 
         // Attempt to capture the child output
-        let mut output = child
-            .wait_with_output()
-            .await
-            .map_err(|e| map_io_to_process_error(e, &self.bin))?;
+        let mut output = self.wait_for_output(child, None).await?;
 
         if output.stderr.is_empty() {
             output.stderr = captured_stderr.read().unwrap().join("\n").into_bytes();
@@ -262,11 +449,52 @@ impl Command {
         self
     }
 
+    /// Run the child in a new process group, detached from the terminal's input,
+    /// so that signals reach the entire tree of processes it spawns.
+    pub fn new_process_group(&mut self) -> &mut Command {
+        self.cmd.stdin(Stdio::null());
+        self.spawn_in_process_group();
+        self
+    }
+
     pub fn no_error_on_failure(&mut self) -> &mut Command {
         self.error = false;
         self
     }
 
+    pub fn timeout(&mut self, duration: Duration) -> &mut Command {
+        self.timeout = Some(duration);
+
+        // Run the child in a new process group, so that the entire
+        // tree can be killed when the timeout is reached
+        self.spawn_in_process_group();
+
+        self
+    }
+
+    fn spawn_in_process_group(&mut self) {
+        if self.new_group {
+            return;
+        }
+
+        self.new_group = true;
+
+        // Children reading from the terminal are left in the foreground group,
+        // otherwise they would be stopped when reading (SIGTTIN), and not receive
+        // Ctrl+C from the terminal. Stdio has already been redirected when this runs,
+        // so we check the child's.
+        #[cfg(unix)]
+        unsafe {
+            self.cmd.pre_exec(|| {
+                if libc::isatty(libc::STDIN_FILENO) == 0 {
+                    libc::setpgid(0, 0);
+                }
+
+                Ok(())
+            });
+        }
+    }
+
     pub fn output_to_error(&self, output: &Output, with_message: bool) -> MoonError {
         let code = output.status.code().unwrap_or(-1);
 
@@ -279,6 +507,70 @@ impl Command {
         MoonError::ProcessNonZeroWithOutput(self.bin.clone(), code, message)
     }
 
+    async fn wait_for_output(
+        &self,
+        mut child: Child,
+        input: Option<&str>,
+    ) -> Result<Output, MoonError> {
+        let process = RunningProcess::track(&child);
+
+        if let Some(input) = input {
+            let mut stdin = child.stdin.take().unwrap();
+            stdin.write_all(input.as_bytes()).await.unwrap();
+            drop(stdin);
+        }
+
+        let duration = match self.timeout {
+            Some(duration) => duration,
+            None => {
+                return child
+                    .wait_with_output()
+                    .await
+                    .map_err(|e| map_io_to_process_error(e, &self.bin));
+            }
+        };
+
+        // Unlike `wait_with_output`, this only borrows the child,
+        // so that it can still be killed and reaped after timing out
+        let stdout = read_to_end(child.stdout.take());
+        let stderr = read_to_end(child.stderr.take());
+        let output = async { tokio::try_join!(child.wait(), stdout, stderr) };
+
+        match time::timeout(duration, output).await {
+            Ok(output) => {
+                let (status, stdout, stderr) =
+                    output.map_err(|e| map_io_to_process_error(e, &self.bin))?;
+
+                Ok(Output {
+                    status,
+                    stdout,
+                    stderr,
+                })
+            }
+            Err(_) => {
+                trace!(
+                    target: "moon:utils",
+                    "Process {} timed out after {:?}, killing it",
+                    color::shell(&self.bin),
+                    duration
+                );
+
+                if let Some(pid) = process.pid {
+                    send_signal(pid, process.own_group, ProcessSignal::Kill);
+                }
+
+                // Kill the child itself when not in its own group,
+                // and wait for it to exit, so that it's not left a zombie
+                let _ = child.kill().await;
+
+                Err(MoonError::ProcessTimeout(
+                    self.bin.clone(),
+                    duration.as_secs(),
+                ))
+            }
+        }
+    }
+
     fn handle_nonzero_status(&self, output: &Output) -> Result<(), MoonError> {
         if self.error && !output.status.success() {
             return Err(self.output_to_error(output, true));
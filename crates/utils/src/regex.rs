@@ -9,6 +9,9 @@ lazy_static! {
     pub static ref ID_PATTERN: Regex = Regex::new(&format!("^{}$", *ID_GROUP)).unwrap();
     pub static ref TARGET_PATTERN: Regex = Regex::new(
         "^(?P<project>(?:[A-Za-z]{1}[0-9A-Za-z_-]*|\\^|~))?:(?P<task>[A-Za-z]{1}[0-9A-Za-z_-]*)$").unwrap();
+    // Tasks may also be a wildcard (`*`) or a glob (`lint-*`) when running targets
+    pub static ref TARGET_WILDCARD_PATTERN: Regex = Regex::new(
+        "^(?P<project>(?:[A-Za-z]{1}[0-9A-Za-z_-]*|\\^|~))?:(?P<task>[A-Za-z*?]{1}[0-9A-Za-z_*?-]*)$").unwrap();
 
     // Token function: `@func(arg)`
     static ref TOKEN_GROUP: &'static str = "([0-9A-Za-z_-]+)";
@@ -16,12 +19,19 @@ lazy_static! {
     pub static ref TOKEN_FUNC_PATTERN: Regex = Regex::new(&format!("^@([a-z]+)\\({}\\)$", *TOKEN_GROUP)).unwrap();
     pub static ref TOKEN_FUNC_ANYWHERE_PATTERN: Regex = Regex::new(&format!("@([a-z]+)\\({}\\)", *TOKEN_GROUP)).unwrap();
     pub static ref TOKEN_VAR_PATTERN: Regex = Regex::new("\\$([a-zA-Z]+)").unwrap();
+
+    // Environment variable: `$ENV_VAR`
+    pub static ref ENV_VAR_PATTERN: Regex = Regex::new("^\\$([A-Z_]{1}[0-9A-Z_]*)$").unwrap();
 }
 
 pub fn clean_id(id: &str) -> String {
     ID_CLEAN.replace(id, "").to_string()
 }
 
+pub fn matches_env_var(value: &str) -> bool {
+    ENV_VAR_PATTERN.is_match(value)
+}
+
 pub fn matches_id(id: &str) -> bool {
     ID_PATTERN.is_match(id)
 }
@@ -1,20 +1,94 @@
+use moon_config::TaskOutputStyle;
 use petgraph::graph::NodeIndex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+/// Options that apply to all actions, and are configured through the action runner.
+#[derive(Clone, Default)]
+pub struct ActionContext {
+    /// Explain why a target's hash has changed since its last run.
+    pub explain: bool,
+
+    /// Set by the action runner when it receives a termination signal.
+    pub interrupted: Arc<AtomicBool>,
+
+    /// Output style for all targets, overriding the task's `outputStyle` option.
+    pub output_style: Option<TaskOutputStyle>,
+
+    /// Arguments to pass to the commands of primary targets.
+    pub passthrough_args: Vec<String>,
+
+    /// Targets that were explicitly requested, and not ran as a dependency.
+    pub primary_targets: HashSet<String>,
+
+    /// Set by the action runner when only persistent targets are still running,
+    /// and they are being shut down.
+    pub shutting_down: Arc<AtomicBool>,
+}
+
+impl ActionContext {
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::SeqCst);
+    }
+
+    /// Passthrough args only apply to primary targets.
+    pub fn get_passthrough_args(&self, target_id: &str) -> &[String] {
+        if self.is_primary_target(target_id) {
+            &self.passthrough_args
+        } else {
+            &[]
+        }
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+
+    pub fn is_primary_target(&self, target_id: &str) -> bool {
+        self.primary_targets.contains(target_id)
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    pub fn shut_down(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+}
+
 pub struct Attempt {
     pub duration: Option<Duration>,
 
+    /// Exit code of the process. Is `None` when killed by a signal or timeout.
+    pub exit_code: Option<i32>,
+
     pub index: u8,
 
     pub start_time: Instant,
+
+    /// Captured output of the process, written to the action's log.
+    pub stderr: String,
+
+    pub stdout: String,
+
+    /// The process was killed because it exceeded its timeout.
+    pub timed_out: bool,
 }
 
 impl Attempt {
     pub fn new(index: u8) -> Self {
         Attempt {
             duration: None,
+            exit_code: None,
             index,
             start_time: Instant::now(),
+            stderr: String::new(),
+            stdout: String::new(),
+            timed_out: false,
         }
     }
 
@@ -23,15 +97,19 @@ impl Attempt {
     }
 }
 
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub enum ActionStatus {
     Cached,
-    // CachedFromRemote, // TODO
+    CachedFromRemote,
     Failed,
     FailedAndAbort,
+    Interrupted,
     Invalid,
     Passed,
     Running,
-    Skipped, // When nothing happened
+    Skipped,            // When nothing happened
+    SkippedFromFailure, // When a dependency failed
 }
 
 pub struct Action {
@@ -41,6 +119,9 @@ pub struct Action {
 
     pub error: Option<String>,
 
+    /// Hash generated for the action, when applicable.
+    pub hash: Option<String>,
+
     pub label: Option<String>,
 
     pub node_index: NodeIndex,
@@ -56,6 +137,7 @@ impl Action {
             attempts: None,
             duration: None,
             error: None,
+            hash: None,
             label: None,
             node_index,
             start_time: Instant::now(),
@@ -78,6 +160,15 @@ impl Action {
             || matches!(self.status, ActionStatus::FailedAndAbort)
     }
 
+    pub fn was_cached(&self) -> bool {
+        matches!(self.status, ActionStatus::Cached)
+            || matches!(self.status, ActionStatus::CachedFromRemote)
+    }
+
+    pub fn was_interrupted(&self) -> bool {
+        matches!(self.status, ActionStatus::Interrupted)
+    }
+
     pub fn pass(&mut self, status: ActionStatus) {
         self.status = status;
         self.duration = Some(self.start_time.elapsed());
@@ -86,4 +177,14 @@ impl Action {
     pub fn should_abort(&self) -> bool {
         matches!(self.status, ActionStatus::FailedAndAbort)
     }
+
+    pub fn skip_from_failure(&mut self, reason: String) {
+        self.error = Some(reason);
+        self.status = ActionStatus::SkippedFromFailure;
+        self.duration = Some(self.start_time.elapsed());
+    }
+
+    pub fn was_skipped_from_failure(&self) -> bool {
+        matches!(self.status, ActionStatus::SkippedFromFailure)
+    }
 }
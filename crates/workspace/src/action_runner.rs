@@ -1,35 +1,50 @@
-use crate::action::{Action, ActionStatus};
-use crate::actions::{install_node_deps, run_target, setup_toolchain, sync_project};
-use crate::dep_graph::{DepGraph, Node};
+use crate::action::{Action, ActionContext, ActionStatus};
+use crate::actions::{install_node_deps, plan_target, run_target, setup_toolchain, sync_project};
+use crate::dep_graph::{DepGraph, Node, NodeIndex};
 use crate::errors::WorkspaceError;
+use crate::run_plan::{PlannedAction, RunPlan};
+use crate::run_profile::RunProfile;
+use crate::run_report::RunReport;
 use crate::workspace::Workspace;
-use moon_logger::{color, debug, error, trace};
+use futures::stream::{FuturesUnordered, StreamExt};
+use moon_config::TaskOutputStyle;
+use moon_error::MoonError;
+use moon_logger::{color, debug, error, trace, warn};
+use moon_terminal::helpers::strip_style_tokens;
+use moon_utils::fs;
+use moon_utils::process::{signal_running_processes, ProcessSignal};
+use moon_utils::time::elapsed;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::thread;
 use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 use tokio::sync::RwLock;
-use tokio::task;
+use tokio::task::{self, JoinHandle};
+use tokio::time;
 
 const LOG_TARGET: &str = "moon:action-runner";
 
+/// How long running processes are given to exit after a signal, before being killed.
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+// Windows processes can't be gracefully terminated, so kill them instead
+#[cfg(unix)]
+const SHUTDOWN_SIGNAL: ProcessSignal = ProcessSignal::Terminate;
+#[cfg(windows)]
+const SHUTDOWN_SIGNAL: ProcessSignal = ProcessSignal::Kill;
+
 async fn run_action(
     workspace: Arc<RwLock<Workspace>>,
     action: &mut Action,
     action_node: &Node,
-    primary_target: &str,
-    passthrough_args: &[String],
+    context: &ActionContext,
 ) -> Result<(), WorkspaceError> {
     let result = match action_node {
         Node::InstallNodeDeps => install_node_deps(workspace).await,
-        Node::RunTarget(target_id) => {
-            run_target(
-                workspace,
-                action,
-                target_id,
-                primary_target,
-                passthrough_args,
-            )
-            .await
-        }
+        Node::RunTarget(target_id) => run_target(workspace, action, context, target_id).await,
         Node::SetupToolchain => setup_toolchain(workspace).await,
         Node::SyncProject(project_id) => sync_project(workspace, project_id).await,
     };
@@ -53,14 +68,259 @@ async fn run_action(
     Ok(())
 }
 
+fn create_action_log(action: &Action) -> String {
+    let status = match action.status {
+        ActionStatus::Cached => "cached",
+        ActionStatus::CachedFromRemote => "cached from remote",
+        ActionStatus::Failed | ActionStatus::FailedAndAbort => "failed",
+        ActionStatus::Interrupted => "interrupted",
+        ActionStatus::Invalid => "invalid",
+        ActionStatus::Passed => "passed",
+        ActionStatus::Running => "running",
+        ActionStatus::Skipped => "skipped",
+        ActionStatus::SkippedFromFailure => "skipped from failure",
+    };
+
+    let mut lines = vec![
+        format!("Action: {}", action.label.as_deref().unwrap_or("unknown")),
+        format!("Status: {}", status),
+    ];
+
+    if let Some(duration) = action.duration {
+        lines.push(format!("Duration: {}", elapsed(duration)));
+    }
+
+    if let Some(error) = &action.error {
+        lines.push(format!("Error: {}", strip_style_tokens(error)));
+    }
+
+    for attempt in action.attempts.iter().flatten() {
+        lines.push(String::new());
+        lines.push(format!("==> Attempt {}", attempt.index));
+
+        if let Some(duration) = attempt.duration {
+            lines.push(format!("Duration: {}", elapsed(duration)));
+        }
+
+        if let Some(code) = attempt.exit_code {
+            lines.push(format!("Exit code: {}", code));
+        }
+
+        if attempt.timed_out {
+            lines.push(String::from("Timed out: true"));
+        }
+
+        if !attempt.stdout.is_empty() {
+            lines.push(String::from("--- stdout ---"));
+            lines.push(attempt.stdout.trim_end().to_owned());
+        }
+
+        if !attempt.stderr.is_empty() {
+            lines.push(String::from("--- stderr ---"));
+            lines.push(attempt.stderr.trim_end().to_owned());
+        }
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+// Logs are for debugging after the fact, so failing to write one
+// should never fail the action itself.
+async fn write_action_log(workspace: &Workspace, node: &Node, action: &Action) {
+    if let Err(error) = workspace
+        .cache
+        .write_run_log(&node.log_key(), &create_action_log(action))
+        .await
+    {
+        warn!(
+            target: LOG_TARGET,
+            "Failed to write log for action {}: {}",
+            color::muted_light(&node.label()),
+            error
+        );
+    }
+}
+
+async fn write_report_to_path(path: &Path, report: &RunReport) -> Result<(), MoonError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    fs::write_json(path, report, true).await
+}
+
+fn spawn_action(
+    workspace: Arc<RwLock<Workspace>>,
+    graph: Arc<RwLock<DepGraph>>,
+    context: Arc<ActionContext>,
+    node_index: NodeIndex,
+    action_count: usize,
+) -> JoinHandle<Result<Action, WorkspaceError>> {
+    task::spawn(async move {
+        let mut action = Action::new(node_index);
+        let own_graph = graph.read().await;
+
+        if let Some(node) = own_graph.get_node_from_index(node_index) {
+            action.label = Some(node.label());
+
+            let log_target_name = format!("{}:{}", LOG_TARGET, action_count);
+            let log_action_label = color::muted_light(&node.label());
+
+            trace!(
+                target: &log_target_name,
+                "Running action {}",
+                log_action_label
+            );
+
+            run_action(Arc::clone(&workspace), &mut action, node, &context).await?;
+
+            write_action_log(&*workspace.read().await, node, &action).await;
+
+            if action.has_failed() {
+                trace!(
+                    target: &log_target_name,
+                    "Failed to run action {} in {:?}",
+                    log_action_label,
+                    action.duration.unwrap()
+                );
+            } else {
+                trace!(
+                    target: &log_target_name,
+                    "Ran action {} in {:?}",
+                    log_action_label,
+                    action.duration.unwrap()
+                );
+            }
+        } else {
+            action.status = ActionStatus::Invalid;
+
+            return Err(WorkspaceError::DepGraphUnknownNode(node_index.index()));
+        }
+
+        Ok(action)
+    })
+}
+
+/// Decrement the dependency count of every dependent of the node. Once all of a dependent's
+/// dependencies have finished, queue it to run, or skip it if any of them failed.
+fn unblock_dependents(
+    graph: &DepGraph,
+    node_index: NodeIndex,
+    failed_label: Option<&String>,
+    dep_counts: &mut HashMap<NodeIndex, usize>,
+    failed_deps: &mut HashMap<NodeIndex, String>,
+    queue: &mut VecDeque<NodeIndex>,
+    skipped: &mut VecDeque<Action>,
+) {
+    for dependent_index in graph.get_dependents(node_index) {
+        if let Some(label) = failed_label {
+            failed_deps
+                .entry(dependent_index)
+                .or_insert_with(|| label.clone());
+        }
+
+        if let Some(count) = dep_counts.get_mut(&dependent_index) {
+            *count -= 1;
+
+            if *count > 0 {
+                continue;
+            }
+
+            match failed_deps.get(&dependent_index) {
+                Some(label) => {
+                    let mut action = Action::new(dependent_index);
+                    action.label = graph
+                        .get_node_from_index(dependent_index)
+                        .map(|node| node.label());
+
+                    debug!(
+                        target: LOG_TARGET,
+                        "Skipping action {} as its dependency {} failed",
+                        color::muted_light(action.label.as_ref().unwrap()),
+                        color::muted_light(label)
+                    );
+
+                    action.skip_from_failure(format!(
+                        "Skipped because its dependency {} failed",
+                        label
+                    ));
+
+                    skipped.push_back(action);
+                }
+                None => {
+                    queue.push_back(dependent_index);
+                }
+            }
+        }
+    }
+}
+
+// Signals are received in a background task, so that none are missed
+// while the runner is busy handling completed actions.
+fn listen_for_signals(sender: UnboundedSender<ProcessSignal>) -> JoinHandle<()> {
+    #[cfg(unix)]
+    let handle = task::spawn(async move {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let (mut interrupt, mut terminate) = match (
+            signal(SignalKind::interrupt()),
+            signal(SignalKind::terminate()),
+        ) {
+            (Ok(interrupt), Ok(terminate)) => (interrupt, terminate),
+            _ => return,
+        };
+
+        loop {
+            let received = tokio::select! {
+                _ = interrupt.recv() => ProcessSignal::Interrupt,
+                _ = terminate.recv() => ProcessSignal::Terminate,
+            };
+
+            if sender.send(received).is_err() {
+                break;
+            }
+        }
+    });
+
+    #[cfg(windows)]
+    let handle = task::spawn(async move {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if sender.send(ProcessSignal::Interrupt).is_err() {
+                break;
+            }
+        }
+    });
+
+    handle
+}
+
+fn default_concurrency() -> usize {
+    thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+}
+
 pub struct ActionRunner {
     bail: bool,
 
+    /// Max number of actions to run in parallel.
+    concurrency: usize,
+
+    context: ActionContext,
+
     pub duration: Option<Duration>,
 
-    passthrough_args: Vec<String>,
+    /// Record a timeline of all actions in the Chrome trace format.
+    profile: bool,
+
+    /// Additional path to write the run report to, outside of the cache.
+    report_path: Option<PathBuf>,
 
-    primary_target: String,
+    /// Signals received from the process, or sent through an interrupter.
+    signal_receiver: UnboundedReceiver<ProcessSignal>,
+
+    signal_sender: UnboundedSender<ProcessSignal>,
 
     workspace: Arc<RwLock<Workspace>>,
 }
@@ -69,11 +329,23 @@ impl ActionRunner {
     pub fn new(workspace: Workspace) -> Self {
         debug!(target: LOG_TARGET, "Creating action runner",);
 
+        let concurrency = workspace
+            .config
+            .action_runner
+            .concurrency
+            .unwrap_or_else(default_concurrency);
+
+        let (signal_sender, signal_receiver) = mpsc::unbounded_channel();
+
         ActionRunner {
             bail: false,
+            concurrency,
+            context: ActionContext::default(),
             duration: None,
-            passthrough_args: Vec::new(),
-            primary_target: String::new(),
+            profile: false,
+            report_path: None,
+            signal_receiver,
+            signal_sender,
             workspace: Arc::new(RwLock::new(workspace)),
         }
     }
@@ -94,14 +366,90 @@ impl ActionRunner {
         Ok(())
     }
 
+    /// Create a sender that interrupts the current run, as if the process received
+    /// the signal. Running processes are signaled and no new actions are started.
+    pub fn create_interrupter(&self) -> UnboundedSender<ProcessSignal> {
+        self.signal_sender.clone()
+    }
+
+    /// Evict stale hashes and outputs from the cache, when `cache.autoClean` is enabled.
+    /// Clean stale hashes and outputs from the cache, when enabled.
+    /// Failing to clean should never fail a run whose actions have passed.
+    pub async fn clean_stale_cache(&self) {
+        let workspace = self.workspace.read().await;
+        let config = &workspace.config.cache;
+
+        if !config.auto_clean {
+            return;
+        }
+
+        trace!(target: LOG_TARGET, "Cleaning stale cache");
+
+        if let Err(error) = workspace
+            .cache
+            .clean_stale_cache(
+                Duration::from_secs(config.max_age as u64 * 86400),
+                config.max_size.map(|size| size as u64 * 1024 * 1024),
+            )
+            .await
+        {
+            warn!(target: LOG_TARGET, "Failed to clean stale cache: {}", error);
+        }
+    }
+
+    pub fn get_workspace(&self) -> Arc<RwLock<Workspace>> {
+        Arc::clone(&self.workspace)
+    }
+
+    /// Resolve the hash and command of every target in the graph, in the order
+    /// they would be ran, without running anything.
+    pub async fn plan(&self, graph: &DepGraph) -> Result<RunPlan, WorkspaceError> {
+        let workspace = self.workspace.read().await;
+        let mut actions = vec![];
+
+        for node_index in graph.sort_topological()? {
+            let node = graph.get_node_from_index(node_index).unwrap();
+            let mut action = PlannedAction::new(node.label());
+
+            if let Node::RunTarget(target_id) = node {
+                plan_target(&workspace, &mut action, &self.context, target_id).await?;
+            }
+
+            actions.push(action);
+        }
+
+        Ok(RunPlan { actions })
+    }
+
     pub async fn run(&mut self, graph: DepGraph) -> Result<Vec<Action>, WorkspaceError> {
         let start = Instant::now();
         let node_count = graph.graph.node_count();
-        let batches = graph.sort_batched_topological()?;
-        let batches_count = batches.len();
+        let mut dep_counts = graph.count_dependencies()?;
+
+        // Persistent actions never complete, so they are tracked separately
+        let persistent_nodes = graph
+            .graph
+            .node_indices()
+            .filter(|node_index| graph.is_persistent(*node_index))
+            .collect::<HashSet<_>>();
+        let persistent_primary_nodes = persistent_nodes
+            .iter()
+            .filter(|node_index| match graph.get_node_from_index(**node_index) {
+                Some(Node::RunTarget(target_id)) => self.context.is_primary_target(target_id),
+                _ => false,
+            })
+            .cloned()
+            .collect::<HashSet<_>>();
+
+        // Actions that hold the same shared resource are never ran in parallel
+        let mutexes = graph
+            .graph
+            .node_indices()
+            .map(|node_index| (node_index, graph.get_mutexes(node_index).to_vec()))
+            .filter(|(_, resources)| !resources.is_empty())
+            .collect::<HashMap<_, _>>();
         let graph = Arc::new(RwLock::new(graph));
-        let passthrough_args = Arc::new(self.passthrough_args.clone());
-        let primary_target = Arc::new(self.primary_target.clone());
+        let context = Arc::new(self.context.clone());
 
         // Clean the runner state *before* running actions instead of after,
         // so that failing or broken builds can dig into and debug the state!
@@ -109,111 +457,262 @@ impl ActionRunner {
 
         debug!(
             target: LOG_TARGET,
-            "Running {} actions across {} batches", node_count, batches_count
+            "Running {} actions with a concurrency of {}", node_count, self.concurrency
         );
 
-        let mut results: Vec<Action> = vec![];
-
-        for (b, batch) in batches.into_iter().enumerate() {
-            let batch_count = b + 1;
-            let batch_target_name = format!("{}:batch:{}", LOG_TARGET, batch_count);
-            let actions_count = batch.len();
-
-            trace!(
-                target: &batch_target_name,
-                "Running {} actions",
-                actions_count
-            );
-
-            let mut action_handles = vec![];
+        // Actions are queued once all of their dependencies have finished
+        // (or started when persistent), and are started as soon as a slot is available
+        let mut queue = dep_counts
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(node_index, _)| *node_index)
+            .collect::<Vec<NodeIndex>>();
 
-            for (i, node_index) in batch.into_iter().enumerate() {
-                let action_count = i + 1;
-                let workspace_clone = Arc::clone(&self.workspace);
-                let graph_clone = Arc::clone(&graph);
-                let passthrough_args_clone = Arc::clone(&passthrough_args);
-                let primary_target_clone = Arc::clone(&primary_target);
+        queue.sort();
 
-                action_handles.push(task::spawn(async move {
-                    let mut action = Action::new(node_index);
-                    let own_graph = graph_clone.read().await;
+        let mut queue = VecDeque::from(queue);
+        let mut running = FuturesUnordered::new();
+        let mut results: Vec<Action> = vec![];
+        let mut action_count = 0;
+
+        // Actions that were skipped because a dependency failed,
+        // mapped to the label of the action that originally failed
+        let mut failed_deps: HashMap<NodeIndex, String> = HashMap::new();
+        let mut skipped: VecDeque<Action> = VecDeque::new();
+
+        // When interrupted, running processes are given a grace period to exit,
+        // before being forcefully killed. No new actions will be started.
+        // Runners may be ran multiple times (in watch mode), so reset the previous state.
+        while self.signal_receiver.try_recv().is_ok() {}
+
+        context.interrupted.store(false, Ordering::SeqCst);
+        context.shutting_down.store(false, Ordering::SeqCst);
+
+        let signal_listener = listen_for_signals(self.signal_sender.clone());
+        let mut kill_deadline: Option<time::Instant> = None;
+        let mut killed = false;
+
+        // Persistent actions that have started, and have not exited yet
+        let mut persistent_running: HashSet<NodeIndex> = HashSet::new();
+
+        // Shared resources held by running actions, mapped to the holding action
+        let mut held_mutexes: HashMap<String, NodeIndex> = HashMap::new();
+
+        loop {
+            while !context.is_interrupted() {
+                // Actions that need a resource held by a running action stay queued,
+                // without blocking the actions queued after them
+                let is_available = |node_index: &NodeIndex| match mutexes.get(node_index) {
+                    Some(resources) => resources
+                        .iter()
+                        .all(|resource| !held_mutexes.contains_key(resource)),
+                    None => true,
+                };
+
+                // Persistent actions are started after all other queued actions,
+                // and don't count towards the concurrency limit
+                let position = match queue.iter().position(|node_index| {
+                    !persistent_nodes.contains(node_index) && is_available(node_index)
+                }) {
+                    Some(position) => {
+                        if running.len() - persistent_running.len() >= self.concurrency {
+                            break;
+                        }
 
-                    if let Some(node) = own_graph.get_node_from_index(node_index) {
-                        action.label = Some(node.label());
+                        position
+                    }
+                    None => match queue.iter().position(|node_index| {
+                        persistent_nodes.contains(node_index) && is_available(node_index)
+                    }) {
+                        Some(position) => position,
+                        None => break,
+                    },
+                };
+
+                let node_index = queue.remove(position).unwrap();
+
+                if let Some(resources) = mutexes.get(&node_index) {
+                    for resource in resources {
+                        held_mutexes.insert(resource.clone(), node_index);
+                    }
+                }
 
-                        let log_target_name =
-                            format!("{}:batch:{}:{}", LOG_TARGET, batch_count, action_count);
-                        let log_action_label = color::muted_light(&node.label());
+                action_count += 1;
+
+                running.push(spawn_action(
+                    Arc::clone(&self.workspace),
+                    Arc::clone(&graph),
+                    Arc::clone(&context),
+                    node_index,
+                    action_count,
+                ));
+
+                // Dependents don't wait for persistent actions to complete,
+                // only for them to start
+                if persistent_nodes.contains(&node_index) {
+                    persistent_running.insert(node_index);
+
+                    unblock_dependents(
+                        &*graph.read().await,
+                        node_index,
+                        None,
+                        &mut dep_counts,
+                        &mut failed_deps,
+                        &mut queue,
+                        &mut skipped,
+                    );
+                }
+            }
 
-                        trace!(
-                            target: &log_target_name,
-                            "Running action {}",
-                            log_action_label
-                        );
+            // Once only persistent actions are running, and none of them are primary,
+            // there's nothing left that can run alongside them, so shut them down.
+            // Anything still queued is waiting on a resource they hold.
+            if !persistent_running.is_empty()
+                && running.len() == persistent_running.len()
+                && skipped.is_empty()
+                && persistent_running.is_disjoint(&persistent_primary_nodes)
+                && !context.is_interrupted()
+                && !context.is_shutting_down()
+            {
+                debug!(
+                    target: LOG_TARGET,
+                    "Only persistent actions are running, shutting them down"
+                );
+
+                context.shut_down();
+                signal_running_processes(SHUTDOWN_SIGNAL);
+                kill_deadline = Some(time::Instant::now() + SHUTDOWN_GRACE_PERIOD);
+            }
 
-                        run_action(
-                            workspace_clone,
-                            &mut action,
-                            node,
-                            &primary_target_clone,
-                            &passthrough_args_clone,
-                        )
-                        .await?;
-
-                        if action.has_failed() {
-                            trace!(
-                                target: &log_target_name,
-                                "Failed to run action {} in {:?}",
-                                log_action_label,
-                                action.duration.unwrap()
+            // Wait for the next action to complete,
+            // while also handling and propagating errors
+            let next = match skipped.pop_front() {
+                Some(result) => Some(Ok(Ok(result))),
+                None => tokio::select! {
+                    next = running.next() => next,
+                    Some(signal) = self.signal_receiver.recv() => {
+                        if context.is_interrupted() {
+                            warn!(
+                                target: LOG_TARGET,
+                                "Received another {:?} signal, killing running processes",
+                                signal
                             );
+
+                            signal_running_processes(ProcessSignal::Kill);
+                            killed = true;
                         } else {
-                            trace!(
-                                target: &log_target_name,
-                                "Ran action {} in {:?}",
-                                log_action_label,
-                                action.duration.unwrap()
+                            warn!(
+                                target: LOG_TARGET,
+                                "Received {:?} signal, waiting up to {:?} for running processes to exit",
+                                signal,
+                                SHUTDOWN_GRACE_PERIOD
                             );
+
+                            context.interrupt();
+                            signal_running_processes(signal);
+                            kill_deadline = Some(time::Instant::now() + SHUTDOWN_GRACE_PERIOD);
                         }
-                    } else {
-                        action.status = ActionStatus::Invalid;
 
-                        return Err(WorkspaceError::DepGraphUnknownNode(node_index.index()));
+                        continue;
                     }
+                    _ = time::sleep_until(kill_deadline.unwrap_or_else(time::Instant::now)),
+                        if kill_deadline.is_some() && !killed =>
+                    {
+                        warn!(
+                            target: LOG_TARGET,
+                            "Running processes did not exit in time, killing them"
+                        );
 
-                    Ok(action)
-                }));
-            }
+                        signal_running_processes(ProcessSignal::Kill);
+                        killed = true;
 
-            // Wait for all actions in this batch to complete,
-            // while also handling and propagating errors
-            for handle in action_handles {
-                match handle.await {
-                    Ok(Ok(result)) => {
-                        if result.should_abort() {
-                            error!(
-                                target: &batch_target_name,
-                                "Encountered a critical error, aborting the action runner"
-                            );
-                        }
+                        continue;
+                    }
+                },
+            };
 
-                        if self.bail && result.error.is_some() || result.should_abort() {
-                            return Err(WorkspaceError::ActionRunnerFailure(result.error.unwrap()));
-                        }
+            let result = match next {
+                Some(Ok(Ok(result))) => result,
+                Some(Ok(Err(e))) => {
+                    self.stop_running_actions(&context, &mut running).await;
+                    signal_listener.abort();
 
-                        results.push(result);
-                    }
-                    Ok(Err(e)) => {
-                        return Err(e);
-                    }
-                    Err(e) => {
-                        return Err(WorkspaceError::ActionRunnerFailure(e.to_string()));
-                    }
+                    return Err(e);
+                }
+                Some(Err(e)) => {
+                    self.stop_running_actions(&context, &mut running).await;
+                    signal_listener.abort();
+
+                    return Err(WorkspaceError::ActionRunnerFailure(e.to_string()));
                 }
+                None => break,
+            };
+
+            if result.should_abort() {
+                error!(
+                    target: LOG_TARGET,
+                    "Encountered a critical error, aborting the action runner"
+                );
+            }
+
+            if self.bail && result.error.is_some() || result.should_abort() {
+                let error = result.error.clone().unwrap();
+
+                results.push(result);
+                results.extend(self.stop_running_actions(&context, &mut running).await);
+
+                signal_listener.abort();
+
+                self.duration = Some(start.elapsed());
+                self.write_run_reports(&results, start).await;
+
+                return Err(WorkspaceError::ActionRunnerFailure(error));
             }
+
+            held_mutexes.retain(|_, node_index| *node_index != result.node_index);
+
+            let failed_label = if result.has_failed() {
+                result.label.clone()
+            } else if result.was_skipped_from_failure() {
+                failed_deps.get(&result.node_index).cloned()
+            } else {
+                None
+            };
+
+            // Unblock dependents that were waiting on this action,
+            // or skip them entirely if this action failed. Dependents of
+            // persistent actions were already unblocked when they started.
+            if !persistent_running.remove(&result.node_index) {
+                unblock_dependents(
+                    &*graph.read().await,
+                    result.node_index,
+                    failed_label.as_ref(),
+                    &mut dep_counts,
+                    &mut failed_deps,
+                    &mut queue,
+                    &mut skipped,
+                );
+            }
+
+            results.push(result);
         }
 
+        signal_listener.abort();
+
         self.duration = Some(start.elapsed());
+        self.write_run_reports(&results, start).await;
+
+        if context.is_interrupted() {
+            debug!(
+                target: LOG_TARGET,
+                "Interrupted after running {} of {} actions in {:?}",
+                results.len(),
+                node_count,
+                self.duration.unwrap()
+            );
+
+            return Ok(results);
+        }
 
         debug!(
             target: LOG_TARGET,
@@ -222,16 +721,149 @@ impl ActionRunner {
             self.duration.unwrap()
         );
 
+        self.clean_stale_cache().await;
+
         Ok(results)
     }
 
+    /// Terminate the processes of all running actions, including persistent actions that never
+    /// complete, and wait for them to exit, so that none are orphaned when returning early.
+    /// Actions that have not spawned their process yet are interrupted before doing so.
+    async fn stop_running_actions(
+        &mut self,
+        context: &ActionContext,
+        running: &mut FuturesUnordered<JoinHandle<Result<Action, WorkspaceError>>>,
+    ) -> Vec<Action> {
+        let mut actions = vec![];
+
+        if running.is_empty() {
+            return actions;
+        }
+
+        debug!(
+            target: LOG_TARGET,
+            "Stopping {} running actions, waiting up to {:?} for their processes to exit",
+            running.len(),
+            SHUTDOWN_GRACE_PERIOD
+        );
+
+        // Stopping is not an interruption, unless a signal was received
+        let was_interrupted = context.is_interrupted();
+        let mut received_signal = false;
+        let mut killed = false;
+        let kill_deadline = time::sleep(SHUTDOWN_GRACE_PERIOD);
+
+        tokio::pin!(kill_deadline);
+
+        context.interrupt();
+        signal_running_processes(SHUTDOWN_SIGNAL);
+
+        loop {
+            tokio::select! {
+                next = running.next() => match next {
+                    Some(Ok(Ok(action))) => actions.push(action),
+                    Some(_) => {}
+                    None => break,
+                },
+                Some(_) = self.signal_receiver.recv(), if !killed => {
+                    received_signal = true;
+                    killed = true;
+
+                    signal_running_processes(ProcessSignal::Kill);
+                }
+                _ = &mut kill_deadline, if !killed => {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Running processes did not exit in time, killing them"
+                    );
+
+                    killed = true;
+
+                    signal_running_processes(ProcessSignal::Kill);
+                }
+            }
+        }
+
+        if !was_interrupted && !received_signal {
+            context.interrupted.store(false, Ordering::SeqCst);
+        }
+
+        actions
+    }
+
+    /// Write a report of all actions to the cache, and to the configured report path,
+    /// and a profile when enabled. Failing to write these should never fail the run.
+    async fn write_run_reports(&self, results: &[Action], start: Instant) {
+        let report = RunReport::new(results, self.duration.unwrap_or_default());
+        let workspace = self.workspace.read().await;
+
+        if self.profile {
+            let profile = RunProfile::new(results, start);
+
+            if let Err(error) = workspace.cache.write_run_profile(&profile).await {
+                warn!(target: LOG_TARGET, "Failed to write run profile: {}", error);
+            }
+        }
+
+        if let Err(error) = workspace.cache.write_run_report(&report).await {
+            warn!(target: LOG_TARGET, "Failed to write run report: {}", error);
+        }
+
+        if let Some(path) = &self.report_path {
+            trace!(
+                target: LOG_TARGET,
+                "Writing run report to {}",
+                color::path(path)
+            );
+
+            if let Err(error) = write_report_to_path(path, &report).await {
+                warn!(
+                    target: LOG_TARGET,
+                    "Failed to write run report to {}: {}",
+                    color::path(path),
+                    error
+                );
+            }
+        }
+    }
+
+    /// Whether the last run was interrupted by a signal.
+    pub fn was_interrupted(&self) -> bool {
+        self.context.is_interrupted()
+    }
+
+    pub fn set_concurrency(&mut self, concurrency: usize) -> &mut Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn set_explain(&mut self, explain: bool) -> &mut Self {
+        self.context.explain = explain;
+        self
+    }
+
+    pub fn set_output_style(&mut self, style: Option<TaskOutputStyle>) -> &mut Self {
+        self.context.output_style = style;
+        self
+    }
+
     pub fn set_passthrough_args(&mut self, args: Vec<String>) -> &mut Self {
-        self.passthrough_args = args;
+        self.context.passthrough_args = args;
+        self
+    }
+
+    pub fn set_primary_targets(&mut self, targets: Vec<String>) -> &mut Self {
+        self.context.primary_targets = targets.into_iter().collect();
+        self
+    }
+
+    pub fn set_profile(&mut self, profile: bool) -> &mut Self {
+        self.profile = profile;
         self
     }
 
-    pub fn set_primary_target(&mut self, target: &str) -> &mut Self {
-        self.primary_target = target.to_owned();
+    pub fn set_report_path(&mut self, path: PathBuf) -> &mut Self {
+        self.report_path = Some(path);
         self
     }
 }
@@ -2,6 +2,8 @@ use crate::{Workspace, WorkspaceError};
 use moon_hasher::TargetHasher;
 use moon_project::{ExpandedFiles, Project, Task};
 use moon_utils::path::path_to_string;
+use std::collections::BTreeMap;
+use std::env;
 use std::path::Path;
 
 fn convert_paths_to_strings(
@@ -61,6 +63,17 @@ pub async fn create_target_hasher(
         hasher.hash_tsconfig_json(&tsconfig);
     }
 
+    // Hash the runtime values of environment variables declared as inputs,
+    // so that changing a variable will bust the cache
+    if !task.input_vars.is_empty() {
+        hasher.hash_input_vars(
+            task.input_vars
+                .iter()
+                .map(|var| (var.clone(), env::var(var).unwrap_or_default()))
+                .collect::<BTreeMap<_, _>>(),
+        );
+    }
+
     // For input files, hash them with the vcs layer first
     if !task.input_paths.is_empty() {
         let files = convert_paths_to_strings(&task.input_paths, &workspace.root)?;
@@ -5,6 +5,6 @@ mod setup_toolchain;
 mod sync_project;
 
 pub use install_node_deps::install_node_deps;
-pub use run_target::run_target;
+pub use run_target::{plan_target, run_target};
 pub use setup_toolchain::setup_toolchain;
 pub use sync_project::sync_project;
@@ -1,9 +1,12 @@
-use crate::action::{Action, ActionStatus, Attempt};
+use crate::action::{Action, ActionContext, ActionStatus, Attempt};
 use crate::actions::hashing::create_target_hasher;
 use crate::errors::WorkspaceError;
+use crate::run_plan::{PlannedAction, PlannedCommand};
 use crate::workspace::Workspace;
 use moon_cache::RunTargetState;
-use moon_config::TaskType;
+use moon_config::{TaskOutputStyle, TaskType};
+use moon_error::MoonError;
+use moon_hasher::TargetHasher;
 use moon_logger::{color, debug, trace, warn};
 use moon_project::{Project, Target, Task};
 use moon_terminal::output::{label_checkpoint, Checkpoint};
@@ -11,8 +14,9 @@ use moon_toolchain::{get_path_env_var, Executable};
 use moon_utils::process::{output_to_string, Command, Output};
 use moon_utils::{is_ci, is_test_env, path, string_vec, time};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
 const LOG_TARGET: &str = "moon:action:run-target";
@@ -226,12 +230,110 @@ async fn create_target_command(
     Ok(command)
 }
 
+/// Create the command for the task, with passthrough args and runner settings applied.
+async fn create_runnable_command(
+    workspace: &Workspace,
+    project: &Project,
+    task: &Task,
+    passthrough_args: &[String],
+) -> Result<Command, WorkspaceError> {
+    let mut command = create_target_command(workspace, project, task).await?;
+    command.args(passthrough_args);
+
+    if workspace
+        .config
+        .action_runner
+        .inherit_colors_for_piped_tasks
+    {
+        command.inherit_colors();
+    }
+
+    if let Some(timeout) = task
+        .options
+        .timeout
+        .or(workspace.config.action_runner.default_timeout)
+    {
+        command.timeout(Duration::from_secs(timeout));
+    }
+
+    // Persistent tasks are terminated once their dependents have completed,
+    // and wrappers (shells, package managers) don't forward signals to
+    // their children, so the entire tree must be signaled
+    if task.options.persistent {
+        command.new_process_group();
+    }
+
+    Ok(command)
+}
+
+/// Download the hash from the remote cache (if configured) and restore
+/// outputs from it. Remote failures are treated as a cache miss.
+async fn hydrate_from_remote(
+    workspace: &Workspace,
+    hash: &str,
+    project_root: &Path,
+    output_paths: &[PathBuf],
+) -> Result<bool, WorkspaceError> {
+    match workspace.cache.download_from_remote(hash).await {
+        Ok(true) => Ok(workspace
+            .cache
+            .hydrate_from_out(hash, project_root, output_paths)
+            .await?),
+        Ok(false) => Ok(false),
+        Err(error) => {
+            warn!(
+                target: LOG_TARGET,
+                "Failed to download hash {} from the remote cache: {}",
+                color::symbol(hash),
+                error
+            );
+
+            Ok(false)
+        }
+    }
+}
+
+/// Compute the hash and command of a target without running it, for dry runs.
+/// The remote cache is not checked, so a miss may still be a hit when ran.
+pub async fn plan_target(
+    workspace: &Workspace,
+    action: &mut PlannedAction,
+    context: &ActionContext,
+    target_id: &str,
+) -> Result<(), WorkspaceError> {
+    let passthrough_args = context.get_passthrough_args(target_id);
+    let (project_id, task_id) = Target::parse(target_id)?.ids()?;
+    let project = workspace.projects.load(&project_id)?;
+    let task = project.get_task(&task_id)?;
+
+    let hasher = create_target_hasher(workspace, &project, task, passthrough_args).await?;
+    let hash = hasher.to_hash();
+    let cache = workspace.cache.cache_run_target_state(target_id).await?;
+
+    let command = create_runnable_command(workspace, &project, task, passthrough_args).await?;
+
+    action.cache_hit = Some(
+        task.is_cacheable()
+            && cache.item.hash == hash
+            && (task.output_paths.is_empty()
+                || workspace.cache.get_output_archive_path(&hash).exists()),
+    );
+    action.command = Some(PlannedCommand {
+        args: command.get_args(),
+        bin: command.get_bin_name().to_owned(),
+        cwd: command.get_cwd(),
+        env: command.get_envs(),
+    });
+    action.hash = Some(hash);
+
+    Ok(())
+}
+
 pub async fn run_target(
     workspace: Arc<RwLock<Workspace>>,
     action: &mut Action,
+    context: &ActionContext,
     target_id: &str,
-    primary_target: &str,
-    passthrough_args: &[String],
 ) -> Result<ActionStatus, WorkspaceError> {
     debug!(
         target: LOG_TARGET,
@@ -239,18 +341,46 @@ pub async fn run_target(
         color::id(target_id)
     );
 
-    let workspace = workspace.read().await;
-    let mut cache = workspace.cache.cache_run_target_state(target_id).await?;
+    let workspace_lock = workspace;
+    let workspace = workspace_lock.read().await;
+
+    // Other moon processes may be writing the run state of the same target,
+    // so only read it while holding the lock. Locks are released when dropped.
+    // The lock is *not* held while running, as persistent targets never finish.
+    let mut cache = {
+        let _target_lock = workspace.cache.lock_target(target_id).await?;
+
+        workspace.cache.cache_run_target_state(target_id).await?
+    };
 
     // Gather the project and task
-    let is_primary = primary_target == target_id;
+    let is_primary = context.is_primary_target(target_id);
+    let passthrough_args = context.get_passthrough_args(target_id);
     let (project_id, task_id) = Target::parse(target_id)?.ids()?;
     let project = workspace.projects.load(&project_id)?;
     let task = project.get_task(&task_id)?;
 
+    // The run's output style takes precedence over the task's, otherwise
+    // only the primary target (or every target in CI) is streamed.
+    // Persistent targets never complete, so their output can't be buffered.
+    let output_style = context
+        .output_style
+        .clone()
+        .or_else(|| task.options.output_style.clone())
+        .unwrap_or(if is_primary || is_ci() && !is_test_env() {
+            TaskOutputStyle::Stream
+        } else if task.options.persistent {
+            TaskOutputStyle::Prefix
+        } else {
+            TaskOutputStyle::Buffer
+        });
+
     // Abort early if this build has already been cached/hashed
     let hasher = create_target_hasher(&workspace, &project, task, passthrough_args).await?;
     let hash = hasher.to_hash();
+    let hash_lock = workspace.cache.lock_hash(&hash).await?;
+
+    action.hash = Some(hash.clone());
 
     debug!(
         target: LOG_TARGET,
@@ -259,62 +389,140 @@ pub async fn run_target(
         color::id(target_id)
     );
 
-    if cache.item.hash == hash {
+    let is_cacheable = task.is_cacheable();
+    let output_paths = task.output_paths.iter().cloned().collect::<Vec<_>>();
+
+    if !is_cacheable {
         debug!(
             target: LOG_TARGET,
-            "Hash exists for {}, aborting run",
+            "Caching is disabled for target {}, skipping hash checks",
+            color::id(target_id),
+        );
+    } else if context.explain && cache.item.hash != hash {
+        explain_hash_change(&workspace, target_id, &cache.item.hash, &hasher).await?;
+    }
+
+    if is_cacheable && cache.item.hash == hash {
+        if workspace
+            .cache
+            .hydrate_from_out(&hash, &project.root, &output_paths)
+            .await?
+        {
+            workspace.cache.touch_hash(&hash).await?;
+
+            debug!(
+                target: LOG_TARGET,
+                "Hash exists for {}, aborting run",
+                color::id(target_id),
+            );
+
+            println!(
+                "{} {}",
+                label_checkpoint(target_id, Checkpoint::Pass),
+                color::muted("(cached)")
+            );
+
+            if matches!(output_style, TaskOutputStyle::Prefix) {
+                print_cache_item(&cache.item, Some(&create_output_prefix(target_id, None)));
+            } else {
+                print_cache_item(&cache.item, None);
+            }
+
+            return Ok(ActionStatus::Cached);
+        }
+
+        debug!(
+            target: LOG_TARGET,
+            "Hash exists for {} but outputs are missing from the cache, running target",
+            color::id(target_id),
+        );
+    }
+
+    // Otherwise attempt to pull the hash and its outputs from the remote cache
+    if is_cacheable && hydrate_from_remote(&workspace, &hash, &project.root, &output_paths).await? {
+        debug!(
+            target: LOG_TARGET,
+            "Hash exists in remote cache for {}, aborting run",
             color::id(target_id),
         );
 
         println!(
             "{} {}",
             label_checkpoint(target_id, Checkpoint::Pass),
-            color::muted("(cached)")
+            color::muted("(cached from remote)")
         );
 
-        print_cache_item(&cache.item);
+        cache.item.exit_code = 0;
+        cache.item.hash = hash;
+        cache.item.last_run_time = cache.now_millis();
+        cache.item.stderr = String::new();
+        cache.item.stdout = String::new();
+
+        let _target_lock = workspace.cache.lock_target(target_id).await?;
 
-        return Ok(ActionStatus::Cached);
+        cache.save().await?;
+
+        return Ok(ActionStatus::CachedFromRemote);
     }
 
-    // Build the command to run based on the task
-    let mut command = create_target_command(&workspace, &project, task).await?;
-    command.args(passthrough_args);
+    // The runner may have been interrupted while hashing or waiting on locks
+    if context.is_interrupted() {
+        return Ok(ActionStatus::Interrupted);
+    }
 
-    if workspace
-        .config
-        .action_runner
-        .inherit_colors_for_piped_tasks
-    {
-        command.inherit_colors();
+    // Or everything that depends on this persistent target has already completed
+    if task.options.persistent && context.is_shutting_down() {
+        return Ok(ActionStatus::Skipped);
     }
 
+    // Build the command to run based on the task
+    let mut command = create_runnable_command(&workspace, &project, task, passthrough_args).await?;
+
+    // Processes may run for a long time (or forever when persistent), so release
+    // the workspace and hash lock while running, so that other actions (and other
+    // moon processes) are not blocked on it. Both are re-acquired afterwards.
+    drop(hash_lock);
+    drop(workspace);
+
     // Run the command as a child process and capture its output.
     // If the process fails and `retry_count` is greater than 0,
     // attempt the process again in case it passes.
     let attempt_total = task.options.retry_count + 1;
     let mut attempt_index = 1;
     let mut attempts = vec![];
-    let stream_output = is_primary || is_ci() && !is_test_env();
     let output;
 
     loop {
         let mut attempt = Attempt::new(attempt_index);
 
-        let possible_output = if stream_output {
-            // Print label *before* output is streamed since it may stay open forever,
-            // or it may use ANSI escape codes to alter the terminal.
-            print_target_label(target_id, &attempt, attempt_total, Checkpoint::Pass);
+        let possible_output = match output_style {
+            TaskOutputStyle::Stream => {
+                // Print label *before* output is streamed since it may stay open forever,
+                // or it may use ANSI escape codes to alter the terminal.
+                print_target_label(target_id, &attempt, attempt_total, Checkpoint::Pass);
 
-            // If this target matches the primary target (the last task to run),
-            // then we want to stream the output directly to the parent (inherit mode).
-            command.exec_stream_and_capture_output().await
-        } else {
-            print_target_label(target_id, &attempt, attempt_total, Checkpoint::Start);
+                // Stream the output directly to the parent (inherit mode).
+                command.exec_stream_and_capture_output().await
+            }
+            TaskOutputStyle::Prefix => {
+                print_target_label(target_id, &attempt, attempt_total, Checkpoint::Start);
+
+                // Stream the output as it happens, but prefix each line with the target,
+                // as it will be interleaved with the output of other targets.
+                command
+                    .exec_stream_and_capture_output_with_prefix(&create_output_prefix(
+                        target_id,
+                        Some((&attempt, attempt_total)),
+                    ))
+                    .await
+            }
+            TaskOutputStyle::Buffer => {
+                print_target_label(target_id, &attempt, attempt_total, Checkpoint::Start);
 
-            // Otherwise we run the process in the background and write the output
-            // once it has completed.
-            command.exec_capture_output().await
+                // Otherwise we run the process in the background and write the output
+                // once it has completed.
+                command.exec_capture_output().await
+            }
         };
 
         attempt.done();
@@ -322,18 +530,45 @@ pub async fn run_target(
         match possible_output {
             // zero and non-zero exit codes
             Ok(out) => {
-                if stream_output {
-                    handle_streamed_output(target_id, &attempt, attempt_total, &out);
-                } else {
-                    handle_captured_output(target_id, &attempt, attempt_total, &out);
+                match output_style {
+                    TaskOutputStyle::Stream => {
+                        handle_streamed_output(target_id, &attempt, attempt_total, &out);
+                    }
+                    TaskOutputStyle::Prefix => {
+                        handle_prefixed_output(target_id, &attempt, attempt_total, &out);
+                    }
+                    TaskOutputStyle::Buffer => {
+                        handle_captured_output(target_id, &attempt, attempt_total, &out);
+                    }
+                };
+
+                attempt.exit_code = out.status.code();
+                attempt.stderr = output_to_string(&out.stderr);
+                attempt.stdout = output_to_string(&out.stdout);
+                attempts.push(attempt);
+
+                // The process was most likely terminated by the signal, so its result
+                // can't be trusted. Avoid retrying it, or caching its hash and outputs.
+                if context.is_interrupted() {
+                    action.attempts = Some(attempts);
+
+                    return Ok(ActionStatus::Interrupted);
                 }
 
-                attempts.push(attempt);
+                // Persistent targets are expected to be terminated once
+                // everything that depends on them has completed
+                if task.options.persistent && context.is_shutting_down() {
+                    action.attempts = Some(attempts);
+
+                    return Ok(ActionStatus::Passed);
+                }
 
                 if out.status.success() {
                     output = out;
                     break;
                 } else if attempt_index >= attempt_total {
+                    action.attempts = Some(attempts);
+
                     return Err(WorkspaceError::Moon(command.output_to_error(&out, false)));
                 } else {
                     attempt_index += 1;
@@ -346,38 +581,98 @@ pub async fn run_target(
                     );
                 }
             }
+            // process was killed after exceeding its timeout
+            Err(MoonError::ProcessTimeout(bin, seconds)) => {
+                attempt.timed_out = true;
+
+                print_target_label(target_id, &attempt, attempt_total, Checkpoint::Fail);
+
+                attempts.push(attempt);
+
+                if attempt_index >= attempt_total {
+                    action.attempts = Some(attempts);
+
+                    return Err(WorkspaceError::Moon(MoonError::ProcessTimeout(
+                        bin, seconds,
+                    )));
+                } else {
+                    attempt_index += 1;
+
+                    warn!(
+                        target: LOG_TARGET,
+                        "Target {} timed out, running again with attempt {}",
+                        color::target(target_id),
+                        attempt_index
+                    );
+                }
+            }
             // process itself failed
             Err(error) => {
+                attempts.push(attempt);
+                action.attempts = Some(attempts);
+
                 return Err(WorkspaceError::Moon(error));
             }
         }
     }
 
-    // Hard link outputs to the `.moon/cache/out` folder and to the cloud,
-    // so that subsequent builds are faster, and any local outputs
-    // can be rehydrated easily.
-    for output_path in &task.output_paths {
+    let workspace = workspace_lock.read().await;
+
+    if is_cacheable {
+        let _hash_lock = workspace.cache.lock_hash(&hash).await?;
+
+        // Archive outputs into the `.moon/cache/out` folder (and the remote cache),
+        // so that subsequent builds are faster, and any local outputs
+        // can be rehydrated easily.
         workspace
             .cache
-            .link_task_output_to_out(&hash, &project.root, output_path)
+            .archive_task_outputs(&hash, &project.root, &output_paths)
             .await?;
+
+        // Save the new hash, and share it with the remote cache.
+        // A remote failure should not fail the run, so we only warn.
+        workspace.cache.save_hash(&hash, &hasher).await?;
+
+        if let Err(error) = workspace.cache.upload_to_remote(&hash).await {
+            warn!(
+                target: LOG_TARGET,
+                "Failed to upload hash {} to the remote cache: {}",
+                color::symbol(&hash),
+                error
+            );
+        }
     }
 
-    // Save the new hash
-    workspace.cache.save_hash(&hash, &hasher).await?;
     action.attempts = Some(attempts);
 
-    // Write the cache with the result and output
+    // Write the cache with the result and output. When caching is disabled,
+    // the hash is not recorded, so that it never matches a future run.
     cache.item.exit_code = output.status.code().unwrap_or(0);
-    cache.item.hash = hash;
+    cache.item.hash = if is_cacheable { hash } else { String::new() };
     cache.item.last_run_time = cache.now_millis();
     cache.item.stderr = output_to_string(&output.stderr);
     cache.item.stdout = output_to_string(&output.stdout);
+
+    let _target_lock = workspace.cache.lock_target(target_id).await?;
+
     cache.save().await?;
 
     Ok(ActionStatus::Passed)
 }
 
+// The target (and the attempt when retrying) to prefix each line of output with,
+// so that the output of targets running in parallel can be told apart.
+fn create_output_prefix(target: &str, attempt: Option<(&Attempt, u8)>) -> String {
+    let label = match attempt {
+        Some((attempt, attempt_total)) if attempt.index > 1 => {
+            format!("{} ({}/{})", target, attempt.index, attempt_total)
+        }
+        _ => target.to_owned(),
+    };
+
+    format!("{} {}", color::target(&label), color::muted("|"))
+}
+
 fn print_target_label(target: &str, attempt: &Attempt, attempt_total: u8, checkpoint: Checkpoint) {
     let failed = matches!(checkpoint, Checkpoint::Fail);
     let mut label = label_checkpoint(target, checkpoint);
@@ -391,6 +686,10 @@ fn print_target_label(target: &str, attempt: &Attempt, attempt_total: u8, checkp
         comments.push(time::elapsed(duration));
     }
 
+    if attempt.timed_out {
+        comments.push(String::from("timed out"));
+    }
+
     if !comments.is_empty() {
         let metadata = color::muted(&format!("({})", comments.join(", ")));
 
@@ -404,7 +703,77 @@ fn print_target_label(target: &str, attempt: &Attempt, attempt_total: u8, checkp
     }
 }
 
-fn print_cache_item(item: &RunTargetState) {
+async fn explain_hash_change(
+    workspace: &Workspace,
+    target_id: &str,
+    previous_hash: &str,
+    hasher: &TargetHasher,
+) -> Result<(), WorkspaceError> {
+    let label = label_checkpoint(target_id, Checkpoint::Start);
+
+    if previous_hash.is_empty() {
+        println!("{} {}", label, color::muted("(no previous run to compare)"));
+
+        return Ok(());
+    }
+
+    let previous = match workspace
+        .cache
+        .load_hash::<TargetHasher>(previous_hash)
+        .await?
+    {
+        Some(previous) => previous,
+        None => {
+            println!(
+                "{} {}",
+                label,
+                color::muted(&format!("(manifest for hash {} not found)", previous_hash))
+            );
+
+            return Ok(());
+        }
+    };
+
+    let diffs = hasher.diff(&previous);
+
+    if diffs.is_empty() {
+        println!(
+            "{} {}",
+            label,
+            color::muted("(hash manifests are identical)")
+        );
+
+        return Ok(());
+    }
+
+    println!(
+        "{} {}",
+        label,
+        color::muted(&format!("(changed since hash {})", previous_hash))
+    );
+
+    for diff in diffs {
+        println!("  {}", diff);
+    }
+
+    println!();
+
+    Ok(())
+}
+
+fn print_cache_item(item: &RunTargetState, prefix: Option<&str>) {
+    if let Some(prefix) = prefix {
+        for line in item.stderr.trim().lines() {
+            eprintln!("{} {}", prefix, line);
+        }
+
+        for line in item.stdout.trim().lines() {
+            println!("{} {}", prefix, line);
+        }
+
+        return;
+    }
+
     if !item.stderr.is_empty() {
         eprintln!("{}", item.stderr.trim());
         eprintln!();
@@ -448,6 +817,21 @@ fn handle_captured_output(target_id: &str, attempt: &Attempt, attempt_total: u8,
     print_output_std(output);
 }
 
+// Output has already been streamed with a prefix, so only print the label
+// with the result, as the start label may be far behind in the output.
+fn handle_prefixed_output(target_id: &str, attempt: &Attempt, attempt_total: u8, output: &Output) {
+    print_target_label(
+        target_id,
+        attempt,
+        attempt_total,
+        if output.status.success() {
+            Checkpoint::Pass
+        } else {
+            Checkpoint::Fail
+        },
+    );
+}
+
 // Only print the label when the process has failed,
 // as the actual output has already been streamed to the console.
 fn handle_streamed_output(target_id: &str, attempt: &Attempt, attempt_total: u8, output: &Output) {
@@ -1,12 +1,15 @@
 use crate::errors::WorkspaceError;
 use moon_logger::{color, debug, trace, warn};
 use moon_project::{
-    ProjectGraph, ProjectID, Target, TargetError, TargetID, TargetProject, TouchedFilePaths,
+    ProjectError, ProjectFilter, ProjectGraph, ProjectID, Target, TargetError, TargetID,
+    TargetProject, TargetTask, TouchedFilePaths,
 };
-use petgraph::algo::toposort;
+use petgraph::algo::{is_cyclic_directed, toposort};
 use petgraph::dot::{Config, Dot};
 use petgraph::graph::DiGraph;
-use petgraph::Graph;
+use petgraph::visit::{Dfs, EdgeRef};
+use petgraph::{Direction, Graph};
+use serde::Serialize;
 use std::collections::{HashMap, HashSet};
 
 pub use petgraph::graph::NodeIndex;
@@ -29,11 +32,44 @@ impl Node {
             Node::SyncProject(id) => format!("SyncProject({})", id),
         }
     }
+
+    /// Key used to group this action's logs within `.moon/cache/runs`.
+    /// Targets use their ID, while other actions are prefixed with an underscore,
+    /// which is not a valid project or task ID, so that they never collide.
+    pub fn log_key(&self) -> String {
+        match self {
+            Node::InstallNodeDeps => String::from("_installNodeDeps"),
+            Node::RunTarget(id) => id.clone(),
+            Node::SetupToolchain => String::from("_setupToolchain"),
+            Node::SyncProject(id) => format!("{}:_syncProject", id),
+        }
+    }
 }
 
 type GraphType = DiGraph<Node, ()>;
 type BatchedTopoSort = Vec<Vec<NodeIndex>>;
 
+#[derive(Serialize)]
+struct JsonNode {
+    id: usize,
+    label: String,
+}
+
+/// An edge from a node (`source`) to a node that it depends on (`target`).
+#[derive(Serialize)]
+struct JsonEdge {
+    source: usize,
+    target: usize,
+}
+
+#[derive(Serialize)]
+struct JsonGraph {
+    /// Node IDs grouped into batches, in the order they can be ran.
+    batches: Vec<Vec<usize>>,
+    edges: Vec<JsonEdge>,
+    nodes: Vec<JsonNode>,
+}
+
 /// A directed acyclic graph (DAG) for the work that needs to be processed, based on a
 /// project or task's dependency chain. This is also known as a "task graph" (not to
 /// be confused with ours) or a "dependency graph".
@@ -46,8 +82,21 @@ pub struct DepGraph {
     /// Reference node for the "install node deps" task.
     install_node_deps_index: NodeIndex,
 
+    /// Shared resources that a target's task holds while running.
+    mutexes: HashMap<NodeIndex, Vec<String>>,
+
+    /// Nodes of targets whose task is persistent (never completes).
+    persistent_indices: HashSet<NodeIndex>,
+
+    /// Only run targets for all projects (`:task`) in projects that match this filter.
+    project_filter: ProjectFilter,
+
     /// Reference node for the "setup toolchain" task.
     setup_toolchain_index: NodeIndex,
+
+    /// Project that owns the current working directory, used to resolve
+    /// `~:task` and `^:task` targets.
+    working_project: Option<ProjectID>,
 }
 
 impl DepGraph {
@@ -68,14 +117,95 @@ impl DepGraph {
             graph,
             index_cache: HashMap::new(),
             install_node_deps_index,
+            mutexes: HashMap::new(),
+            persistent_indices: HashSet::new(),
+            project_filter: ProjectFilter::default(),
             setup_toolchain_index,
+            working_project: None,
+        }
+    }
+
+    /// Count the number of direct dependencies for every node in the graph.
+    pub fn count_dependencies(&self) -> Result<HashMap<NodeIndex, usize>, WorkspaceError> {
+        // Nodes within a cycle would never be unblocked
+        if is_cyclic_directed(&self.graph) {
+            self.detect_cycle()?;
         }
+
+        Ok(self
+            .graph
+            .node_indices()
+            .map(|ix| (ix, self.graph.neighbors(ix).count()))
+            .collect())
+    }
+
+    /// Return all nodes that directly depend on the provided node.
+    pub fn get_dependents(&self, index: NodeIndex) -> Vec<NodeIndex> {
+        self.graph
+            .neighbors_directed(index, Direction::Incoming)
+            .collect()
+    }
+
+    /// Return the shared resources that the node holds while running.
+    pub fn get_mutexes(&self, index: NodeIndex) -> &[String] {
+        self.mutexes
+            .get(&index)
+            .map(|m| m.as_slice())
+            .unwrap_or(&[])
     }
 
     pub fn get_node_from_index(&self, index: NodeIndex) -> Option<&Node> {
         self.graph.node_weight(index)
     }
 
+    /// Whether the node is a target with a persistent task.
+    /// Whether the target, or any target it depends on (transitively), is affected
+    /// by the touched files. Returns false if the target is not in the graph.
+    pub fn is_target_affected(
+        &self,
+        target_id: &str,
+        projects: &ProjectGraph,
+        touched_files: &TouchedFilePaths,
+    ) -> Result<bool, WorkspaceError> {
+        let index = match self.index_cache.get(target_id) {
+            Some(index) => *index,
+            None => return Ok(false),
+        };
+
+        if projects.is_globally_affected(touched_files) {
+            return Ok(true);
+        }
+
+        let mut dfs = Dfs::new(&self.graph, index);
+
+        while let Some(node_index) = dfs.next(&self.graph) {
+            if let Some(Node::RunTarget(id)) = self.get_node_from_index(node_index) {
+                let (project_id, task_id) = Target::parse(id)?.ids()?;
+                let project = projects.load(&project_id)?;
+
+                if project.get_task(&task_id)?.is_affected(touched_files)? {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub fn is_persistent(&self, index: NodeIndex) -> bool {
+        self.persistent_indices.contains(&index)
+    }
+
+    pub fn set_project_filter(&mut self, filter: ProjectFilter) -> &mut Self {
+        self.project_filter = filter;
+        self
+    }
+
+    pub fn set_working_project(&mut self, project_id: Option<ProjectID>) -> &mut Self {
+        self.working_project = project_id;
+        self
+    }
+
     pub fn sort_topological(&self) -> Result<Vec<NodeIndex>, WorkspaceError> {
         let list = match toposort(&self.graph, None) {
             Ok(nodes) => nodes,
@@ -146,14 +276,15 @@ impl DepGraph {
         Ok(batches.into_iter().rev().collect())
     }
 
+    /// Insert the target (and its dependencies) into the graph, and return the IDs
+    /// of all targets that were inserted, as the target may be for all projects.
     pub fn run_target(
         &mut self,
         target: &Target,
         projects: &ProjectGraph,
         touched_files: Option<&TouchedFilePaths>,
-    ) -> Result<usize, WorkspaceError> {
-        let task_id = &target.task_id;
-        let mut inserted_count = 0;
+    ) -> Result<Vec<TargetID>, WorkspaceError> {
+        let mut inserted_targets = vec![];
 
         match &target.project {
             // :task
@@ -161,35 +292,70 @@ impl DepGraph {
                 for project_id in projects.ids() {
                     let project = projects.load(&project_id)?;
 
-                    if project.tasks.contains_key(task_id)
-                        && self
-                            .insert_target(&project_id, task_id, projects, touched_files)?
-                            .is_some()
-                    {
-                        inserted_count += 1;
+                    if !self.project_filter.matches(&project) {
+                        trace!(
+                            target: TARGET,
+                            "Project {} does not match the project filter, skipping",
+                            color::id(&project_id),
+                        );
+
+                        continue;
                     }
+
+                    inserted_targets.extend(self.insert_matching_targets(
+                        &project_id,
+                        target,
+                        projects,
+                        touched_files,
+                        false,
+                    )?);
                 }
             }
             // ^:task
             TargetProject::Deps => {
-                target.fail_with(TargetError::NoProjectDepsInRunContext)?;
+                let project_id =
+                    self.get_working_project(TargetError::NoProjectDepsInRunContext)?;
+                let project = projects.load(&project_id)?;
+                let mut dep_ids = projects.get_dependencies_of(&project)?;
+
+                dep_ids.sort();
+
+                for dep_id in dep_ids {
+                    inserted_targets.extend(self.insert_matching_targets(
+                        &dep_id,
+                        target,
+                        projects,
+                        touched_files,
+                        false,
+                    )?);
+                }
             }
             // project:task
             TargetProject::Id(project_id) => {
-                if self
-                    .insert_target(project_id, task_id, projects, touched_files)?
-                    .is_some()
-                {
-                    inserted_count += 1;
-                }
+                inserted_targets.extend(self.insert_matching_targets(
+                    project_id,
+                    target,
+                    projects,
+                    touched_files,
+                    true,
+                )?);
             }
             // ~:task
             TargetProject::Own => {
-                target.fail_with(TargetError::NoProjectSelfInRunContext)?;
+                let project_id =
+                    self.get_working_project(TargetError::NoProjectSelfInRunContext)?;
+
+                inserted_targets.extend(self.insert_matching_targets(
+                    &project_id,
+                    target,
+                    projects,
+                    touched_files,
+                    true,
+                )?);
             }
         };
 
-        Ok(inserted_count)
+        Ok(inserted_targets)
     }
 
     pub fn run_target_dependents(
@@ -203,16 +369,22 @@ impl DepGraph {
             color::target(&target.id),
         );
 
-        let (project_id, task_id) = target.ids()?;
+        let project_id = match &target.project {
+            TargetProject::Id(project_id) => project_id.clone(),
+            TargetProject::Own => {
+                self.get_working_project(TargetError::NoProjectSelfInRunContext)?
+            }
+            _ => {
+                return Err(WorkspaceError::Project(ProjectError::Target(
+                    TargetError::IdOnly(target.id.clone()),
+                )))
+            }
+        };
         let project = projects.load(&project_id)?;
         let dependents = projects.get_dependents_of(&project)?;
 
         for dependent_id in dependents {
-            let dependent = projects.load(&dependent_id)?;
-
-            if dependent.tasks.contains_key(&task_id) {
-                self.run_target(&Target::new(&dependent_id, &task_id)?, projects, None)?;
-            }
+            self.insert_matching_targets(&dependent_id, target, projects, None, false)?;
         }
 
         Ok(())
@@ -263,6 +435,42 @@ impl DepGraph {
         format!("{:?}", dot)
     }
 
+    /// Return the graph as a JSON string, with its nodes, edges,
+    /// and the batches that nodes would be ran in.
+    pub fn to_json(&self) -> Result<String, WorkspaceError> {
+        let batches = self
+            .sort_batched_topological()?
+            .into_iter()
+            .map(|batch| {
+                let mut ids = batch.iter().map(|ix| ix.index()).collect::<Vec<_>>();
+                ids.sort_unstable();
+                ids
+            })
+            .collect();
+
+        let graph = JsonGraph {
+            batches,
+            edges: self
+                .graph
+                .edge_references()
+                .map(|edge| JsonEdge {
+                    source: edge.source().index(),
+                    target: edge.target().index(),
+                })
+                .collect(),
+            nodes: self
+                .graph
+                .node_indices()
+                .map(|ix| JsonNode {
+                    id: ix.index(),
+                    label: self.graph[ix].label(),
+                })
+                .collect(),
+        };
+
+        Ok(serde_json::to_string_pretty(&graph)?)
+    }
+
     fn detect_cycle(&self) -> Result<(), WorkspaceError> {
         use petgraph::algo::kosaraju_scc;
 
@@ -278,6 +486,60 @@ impl DepGraph {
         Err(WorkspaceError::DepGraphCycleDetected(cycle))
     }
 
+    /// Insert every task of the project that matches the target's task (which may be
+    /// a wildcard or glob), and return the IDs of all targets that were inserted.
+    /// When `required`, a literal task that does not exist in the project will error.
+    fn insert_matching_targets(
+        &mut self,
+        project_id: &str,
+        target: &Target,
+        projects: &ProjectGraph,
+        touched_files: Option<&TouchedFilePaths>,
+        required: bool,
+    ) -> Result<Vec<TargetID>, WorkspaceError> {
+        let project = projects.load(project_id)?;
+        let mut task_ids = vec![];
+
+        match &target.task {
+            TargetTask::Id(task_id) => {
+                if required || project.tasks.contains_key(task_id) {
+                    task_ids.push(task_id.clone());
+                }
+            }
+            _ => {
+                for task_id in project.tasks.keys() {
+                    if target.matches_task(task_id)? {
+                        task_ids.push(task_id.clone());
+                    }
+                }
+
+                task_ids.sort();
+            }
+        };
+
+        let mut inserted_targets = vec![];
+
+        for task_id in task_ids {
+            if self
+                .insert_target(project_id, &task_id, projects, touched_files)?
+                .is_some()
+            {
+                inserted_targets.push(Target::format(project_id, &task_id)?);
+            }
+        }
+
+        Ok(inserted_targets)
+    }
+
+    /// Return the project that owns the working directory, or fail with the
+    /// provided error, as `~:task` and `^:task` targets require it.
+    fn get_working_project(&self, error: TargetError) -> Result<ProjectID, WorkspaceError> {
+        match &self.working_project {
+            Some(id) => Ok(id.clone()),
+            None => Err(WorkspaceError::Project(ProjectError::Target(error))),
+        }
+    }
+
     fn insert_target(
         &mut self,
         project_id: &str,
@@ -336,6 +598,14 @@ impl DepGraph {
         // And we also need to wait on all dependent nodes
         let task = project.get_task(task_id)?;
 
+        if task.options.persistent {
+            self.persistent_indices.insert(node);
+        }
+
+        if !task.options.mutex.is_empty() {
+            self.mutexes.insert(node, task.options.mutex.clone());
+        }
+
         if !task.deps.is_empty() {
             let dep_names: Vec<String> = task
                 .deps
@@ -352,14 +622,11 @@ impl DepGraph {
             );
 
             for dep_target_id in &task.deps {
-                let dep_target = Target::parse(dep_target_id)?;
+                let (dep_project_id, dep_task_id) = Target::parse(dep_target_id)?.ids()?;
 
-                if let Some(dep_node) = self.insert_target(
-                    &dep_target.project_id.unwrap(),
-                    &dep_target.task_id,
-                    projects,
-                    touched_files,
-                )? {
+                if let Some(dep_node) =
+                    self.insert_target(&dep_project_id, &dep_task_id, projects, touched_files)?
+                {
                     self.graph.add_edge(node, dep_node, ());
                 }
             }
@@ -375,7 +642,7 @@ mod tests {
     use insta::assert_snapshot;
     use moon_cache::CacheEngine;
     use moon_config::GlobalProjectConfig;
-    use moon_project::ProjectGraph;
+    use moon_project::{ProjectGraph, ProjectLanguage, ProjectType};
     use moon_utils::test::get_fixtures_dir;
     use std::collections::HashMap;
 
@@ -458,6 +725,32 @@ mod tests {
             sort_batches(graph.sort_batched_topological().unwrap()),
             vec![vec![NodeIndex::new(0)], vec![NodeIndex::new(1)]]
         );
+        assert_eq!(
+            graph.count_dependencies().unwrap(),
+            HashMap::from([(NodeIndex::new(0), 0), (NodeIndex::new(1), 1)])
+        );
+        assert_eq!(
+            graph.get_dependents(NodeIndex::new(0)),
+            vec![NodeIndex::new(1)]
+        );
+        assert!(graph.get_dependents(NodeIndex::new(1)).is_empty());
+    }
+
+    #[test]
+    fn default_graph_to_json() {
+        let graph = DepGraph::default();
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&graph.to_json().unwrap()).unwrap(),
+            serde_json::json!({
+                "batches": [[0], [1]],
+                "edges": [{ "source": 1, "target": 0 }],
+                "nodes": [
+                    { "id": 0, "label": "SetupToolchain" },
+                    { "id": 1, "label": "InstallNodeDeps" },
+                ],
+            })
+        );
     }
 
     #[tokio::test]
@@ -606,6 +899,115 @@ mod tests {
             );
         }
 
+        #[tokio::test]
+        async fn returns_inserted_targets() {
+            let projects = create_tasks_project_graph().await;
+
+            let mut graph = DepGraph::default();
+            let mut targets = graph
+                .run_target(&Target::parse(":build").unwrap(), &projects, None)
+                .unwrap();
+
+            targets.sort();
+
+            assert_eq!(
+                targets,
+                vec![
+                    "basic:build",
+                    "build-a:build",
+                    "build-b:build",
+                    "build-c:build"
+                ]
+            );
+            assert_eq!(
+                graph
+                    .run_target(&Target::new("basic", "lint").unwrap(), &projects, None)
+                    .unwrap(),
+                vec!["basic:lint"]
+            );
+        }
+
+        #[tokio::test]
+        async fn tracks_persistent_targets() {
+            let projects = create_tasks_project_graph().await;
+
+            let mut graph = DepGraph::default();
+            graph
+                .run_target(&Target::new("basic", "build").unwrap(), &projects, None)
+                .unwrap();
+            graph
+                .run_target(&Target::new("basic", "watch").unwrap(), &projects, None)
+                .unwrap();
+
+            assert!(!graph.is_persistent(*graph.index_cache.get("basic:build").unwrap()));
+            assert!(graph.is_persistent(*graph.index_cache.get("basic:watch").unwrap()));
+        }
+
+        #[tokio::test]
+        async fn tracks_target_mutexes() {
+            let projects = create_tasks_project_graph().await;
+
+            let mut graph = DepGraph::default();
+            graph
+                .run_target(&Target::new("basic", "build").unwrap(), &projects, None)
+                .unwrap();
+            graph
+                .run_target(&Target::new("basic", "migrate").unwrap(), &projects, None)
+                .unwrap();
+
+            assert!(graph
+                .get_mutexes(*graph.index_cache.get("basic:build").unwrap())
+                .is_empty());
+            assert_eq!(
+                graph.get_mutexes(*graph.index_cache.get("basic:migrate").unwrap()),
+                ["db"]
+            );
+        }
+
+        #[tokio::test]
+        async fn filters_projects_for_target_all_scope() {
+            let projects = create_tasks_project_graph().await;
+
+            let mut graph = DepGraph::default();
+            graph.set_project_filter(ProjectFilter {
+                types: vec![ProjectType::Application],
+                ..ProjectFilter::default()
+            });
+
+            assert_eq!(
+                graph
+                    .run_target(&Target::parse(":build").unwrap(), &projects, None)
+                    .unwrap(),
+                vec!["build-b:build"]
+            );
+
+            let mut graph = DepGraph::default();
+            graph.set_project_filter(ProjectFilter {
+                languages: vec![ProjectLanguage::JavaScript],
+                ..ProjectFilter::default()
+            });
+
+            assert_eq!(
+                graph
+                    .run_target(&Target::parse(":build").unwrap(), &projects, None)
+                    .unwrap(),
+                vec!["build-c:build"]
+            );
+
+            let mut graph = DepGraph::default();
+            graph.set_project_filter(ProjectFilter {
+                tags: vec![String::from("ui")],
+                ..ProjectFilter::default()
+            });
+
+            assert_eq!(
+                graph
+                    .run_target(&Target::parse(":build").unwrap(), &projects, None)
+                    .unwrap(),
+                vec!["build-b:build"]
+            );
+        }
+
         #[tokio::test]
         async fn runs_all_projects_for_target_all_scope() {
             let projects = create_tasks_project_graph().await;
@@ -648,6 +1050,88 @@ mod tests {
             );
         }
 
+        #[tokio::test]
+        async fn inserts_all_tasks_of_project() {
+            let projects = create_tasks_project_graph().await;
+
+            let mut graph = DepGraph::default();
+
+            assert_eq!(
+                graph
+                    .run_target(&Target::parse("basic:*").unwrap(), &projects, None)
+                    .unwrap(),
+                vec![
+                    "basic:build",
+                    "basic:lint",
+                    "basic:migrate",
+                    "basic:test",
+                    "basic:watch"
+                ]
+            );
+        }
+
+        #[tokio::test]
+        async fn inserts_tasks_matching_glob() {
+            let projects = create_tasks_project_graph().await;
+
+            let mut graph = DepGraph::default();
+
+            assert_eq!(
+                graph
+                    .run_target(&Target::parse("basic:*t").unwrap(), &projects, None)
+                    .unwrap(),
+                vec!["basic:lint", "basic:test"]
+            );
+            assert_eq!(
+                graph
+                    .run_target(&Target::parse(":buil?").unwrap(), &projects, None)
+                    .unwrap(),
+                vec![
+                    "basic:build",
+                    "build-a:build",
+                    "build-b:build",
+                    "build-c:build"
+                ]
+            );
+        }
+
+        #[tokio::test]
+        async fn resolves_deps_scope_from_working_project() {
+            let projects = create_tasks_project_graph().await;
+
+            let mut graph = DepGraph::default();
+            graph.set_working_project(Some("build-a".to_owned()));
+
+            assert_eq!(
+                graph
+                    .run_target(&Target::parse("^:build").unwrap(), &projects, None)
+                    .unwrap(),
+                vec!["basic:build", "build-c:build"]
+            );
+            // Dependencies without the task are skipped
+            assert_eq!(
+                graph
+                    .run_target(&Target::parse("^:lint").unwrap(), &projects, None)
+                    .unwrap(),
+                vec!["basic:lint"]
+            );
+        }
+
+        #[tokio::test]
+        async fn resolves_self_scope_from_working_project() {
+            let projects = create_tasks_project_graph().await;
+
+            let mut graph = DepGraph::default();
+            graph.set_working_project(Some("basic".to_owned()));
+
+            assert_eq!(
+                graph
+                    .run_target(&Target::parse("~:lint").unwrap(), &projects, None)
+                    .unwrap(),
+                vec!["basic:lint"]
+            );
+        }
+
         #[tokio::test]
         #[should_panic(expected = "Project(Target(NoProjectDepsInRunContext))")]
         async fn errors_for_target_deps_scope() {
@@ -763,6 +1247,54 @@ mod tests {
         }
     }
 
+    mod is_target_affected {
+        use super::*;
+
+        #[tokio::test]
+        async fn affected_through_dependency() {
+            let projects = create_tasks_project_graph().await;
+
+            let mut graph = DepGraph::default();
+            graph
+                .run_target(&Target::new("build-a", "build").unwrap(), &projects, None)
+                .unwrap();
+
+            let touched_files = HashSet::from([get_fixtures_dir("tasks").join("basic/file.ts")]);
+
+            assert!(graph
+                .is_target_affected("build-a:build", &projects, &touched_files)
+                .unwrap());
+        }
+
+        #[tokio::test]
+        async fn not_affected_by_unrelated_files() {
+            let projects = create_tasks_project_graph().await;
+
+            let mut graph = DepGraph::default();
+            graph
+                .run_target(&Target::new("build-a", "build").unwrap(), &projects, None)
+                .unwrap();
+
+            let touched_files = HashSet::from([get_fixtures_dir("tasks").join("input-a/a.ts")]);
+
+            assert!(!graph
+                .is_target_affected("build-a:build", &projects, &touched_files)
+                .unwrap());
+        }
+
+        #[tokio::test]
+        async fn not_affected_if_not_in_graph() {
+            let projects = create_tasks_project_graph().await;
+            let graph = DepGraph::default();
+
+            let touched_files = HashSet::from([get_fixtures_dir("tasks").join("basic/file.ts")]);
+
+            assert!(!graph
+                .is_target_affected("basic:build", &projects, &touched_files)
+                .unwrap());
+        }
+    }
+
     mod sync_project {
         use super::*;
 
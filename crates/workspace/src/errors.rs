@@ -3,6 +3,8 @@ use moon_config::constants;
 use moon_error::MoonError;
 use moon_project::ProjectError;
 use moon_toolchain::ToolchainError;
+use notify::Error as NotifyError;
+use serde_json::Error as JsonError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -22,6 +24,12 @@ pub enum WorkspaceError {
     )]
     MissingConfigDir,
 
+    #[error("Unable to find a manifest for hash <symbol>{0}</symbol>. Has it been cleaned?")]
+    MissingHashManifest(String),
+
+    #[error("No logs found for target <target>{0}</target>. Has it been ran?")]
+    MissingRunLog(String),
+
     #[error(
         "Unable to locate a root <file>package.json</file>. Please create one alongside the <file>{}</file> configuration folder.",
         constants::CONFIG_DIRNAME
@@ -52,6 +60,9 @@ pub enum WorkspaceError {
     #[error(transparent)]
     Ignore(#[from] IgnoreError),
 
+    #[error(transparent)]
+    Json(#[from] JsonError),
+
     #[error(transparent)]
     Moon(#[from] MoonError),
 
@@ -60,4 +71,7 @@ pub enum WorkspaceError {
 
     #[error(transparent)]
     Toolchain(#[from] ToolchainError),
+
+    #[error(transparent)]
+    Watcher(#[from] NotifyError),
 }
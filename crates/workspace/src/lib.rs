@@ -3,12 +3,20 @@ mod action_runner;
 mod actions;
 mod dep_graph;
 mod errors;
+mod run_plan;
+mod run_profile;
+mod run_report;
 mod vcs;
+mod watcher;
 mod workspace;
 
-pub use action::{Action, ActionStatus};
+pub use action::{Action, ActionContext, ActionStatus};
 pub use action_runner::ActionRunner;
-pub use dep_graph::DepGraph;
+pub use dep_graph::{DepGraph, Node};
 pub use errors::WorkspaceError;
+pub use run_plan::{PlannedAction, PlannedCommand, RunPlan};
+pub use run_profile::{RunProfile, TraceEvent};
+pub use run_report::{ActionReport, AttemptReport, RunReport};
 pub use vcs::TouchedFiles;
+pub use watcher::FileWatcher;
 pub use workspace::Workspace;
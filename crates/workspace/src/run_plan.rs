@@ -0,0 +1,106 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// The command that would be spawned for a target, with its
+/// resolved arguments, environment variables, and working directory.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedCommand {
+    pub args: Vec<String>,
+
+    pub bin: String,
+
+    pub cwd: Option<PathBuf>,
+
+    /// Only variables set by the task or moon, not those inherited from the parent.
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedAction {
+    /// Whether the hash (and its outputs) exist in the local cache.
+    /// Is `None` for actions that are not hashed.
+    pub cache_hit: Option<bool>,
+
+    pub command: Option<PlannedCommand>,
+
+    pub hash: Option<String>,
+
+    pub label: String,
+}
+
+impl PlannedAction {
+    pub fn new(label: String) -> Self {
+        PlannedAction {
+            cache_hit: None,
+            command: None,
+            hash: None,
+            label,
+        }
+    }
+}
+
+/// Every action in the dependency graph, in the order they would be ran,
+/// as printed by the `--dryRun` option.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunPlan {
+    pub actions: Vec<PlannedAction>,
+}
+
+impl RunPlan {
+    /// Return the plan as a JSON string.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serializes_actions() {
+        let mut action = PlannedAction::new(String::from("RunTarget(app:build)"));
+        action.cache_hit = Some(false);
+        action.hash = Some(String::from("abc123"));
+        action.command = Some(PlannedCommand {
+            args: vec![String::from("--color")],
+            bin: String::from("eslint"),
+            cwd: Some(PathBuf::from("/app")),
+            env: BTreeMap::from([(String::from("MOON_PROJECT_ID"), String::from("app"))]),
+        });
+
+        let plan = RunPlan {
+            actions: vec![PlannedAction::new(String::from("SetupToolchain")), action],
+        };
+
+        assert_eq!(
+            serde_json::to_value(&plan).unwrap(),
+            json!({
+                "actions": [
+                    {
+                        "cacheHit": null,
+                        "command": null,
+                        "hash": null,
+                        "label": "SetupToolchain",
+                    },
+                    {
+                        "cacheHit": false,
+                        "command": {
+                            "args": ["--color"],
+                            "bin": "eslint",
+                            "cwd": "/app",
+                            "env": { "MOON_PROJECT_ID": "app" },
+                        },
+                        "hash": "abc123",
+                        "label": "RunTarget(app:build)",
+                    }
+                ]
+            })
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use crate::action::Action;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+fn to_micros(duration: Duration) -> u64 {
+    duration.as_micros() as u64
+}
+
+/// A single event in the Chrome Trace Event format. Only "complete" (`X`)
+/// and "metadata" (`M`) events are emitted.
+/// https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+#[derive(Debug, Serialize)]
+pub struct TraceEvent {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub args: BTreeMap<String, String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cat: Option<String>,
+
+    /// Duration of the event in microseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dur: Option<u64>,
+
+    pub name: String,
+
+    pub ph: String,
+
+    pub pid: u32,
+
+    pub tid: usize,
+
+    /// Start of the event in microseconds, relative to the start of the run.
+    pub ts: u64,
+}
+
+impl TraceEvent {
+    fn complete(category: &str, name: String, tid: usize, start: Duration, dur: Duration) -> Self {
+        TraceEvent {
+            args: BTreeMap::new(),
+            cat: Some(category.to_owned()),
+            dur: Some(to_micros(dur)),
+            name,
+            ph: String::from("X"),
+            pid: 1,
+            tid,
+            ts: to_micros(start),
+        }
+    }
+
+    fn metadata(name: &str, tid: usize, value: String) -> Self {
+        TraceEvent {
+            args: BTreeMap::from([(String::from("name"), value)]),
+            cat: None,
+            dur: None,
+            name: name.to_owned(),
+            ph: String::from("M"),
+            pid: 1,
+            tid,
+            ts: 0,
+        }
+    }
+}
+
+/// A timeline of every action (and its attempts) that was ran by the action runner,
+/// in the Chrome Trace Event format, which can be opened in `chrome://tracing` or Perfetto.
+/// Actions that overlap are placed on separate "worker" threads.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunProfile {
+    pub display_time_unit: String,
+
+    pub trace_events: Vec<TraceEvent>,
+}
+
+impl RunProfile {
+    pub fn new(actions: &[Action], start: Instant) -> Self {
+        let mut sorted_actions = actions.iter().collect::<Vec<_>>();
+        sorted_actions.sort_by_key(|action| action.start_time);
+
+        // The end time of the last action placed on each worker
+        let mut workers: Vec<Instant> = vec![];
+        let mut events = vec![TraceEvent::metadata(
+            "process_name",
+            0,
+            String::from("moon"),
+        )];
+
+        for action in sorted_actions {
+            let duration = action.duration.unwrap_or_default();
+            let end_time = action.start_time + duration;
+
+            // Place on the first worker that is free, or start a new one
+            let tid = match workers
+                .iter()
+                .position(|worker_end| *worker_end <= action.start_time)
+            {
+                Some(index) => {
+                    workers[index] = end_time;
+                    index + 1
+                }
+                None => {
+                    workers.push(end_time);
+                    workers.len()
+                }
+            };
+
+            let mut event = TraceEvent::complete(
+                "action",
+                action
+                    .label
+                    .clone()
+                    .unwrap_or_else(|| String::from("Unknown")),
+                tid,
+                action.start_time.saturating_duration_since(start),
+                duration,
+            );
+
+            event
+                .args
+                .insert(String::from("status"), format!("{:?}", action.status));
+
+            if let Some(hash) = &action.hash {
+                event.args.insert(String::from("hash"), hash.clone());
+            }
+
+            if let Some(error) = &action.error {
+                event.args.insert(String::from("error"), error.clone());
+            }
+
+            events.push(event);
+
+            for attempt in action.attempts.iter().flatten() {
+                let mut event = TraceEvent::complete(
+                    "attempt",
+                    format!("Attempt {}", attempt.index),
+                    tid,
+                    attempt.start_time.saturating_duration_since(start),
+                    attempt.duration.unwrap_or_default(),
+                );
+
+                if let Some(code) = attempt.exit_code {
+                    event
+                        .args
+                        .insert(String::from("exitCode"), code.to_string());
+                }
+
+                if attempt.timed_out {
+                    event
+                        .args
+                        .insert(String::from("timedOut"), String::from("true"));
+                }
+
+                events.push(event);
+            }
+        }
+
+        for index in 0..workers.len() {
+            events.push(TraceEvent::metadata(
+                "thread_name",
+                index + 1,
+                format!("Worker {}", index + 1),
+            ));
+        }
+
+        RunProfile {
+            display_time_unit: String::from("ms"),
+            trace_events: events,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::{ActionStatus, Attempt};
+    use crate::dep_graph::NodeIndex;
+
+    fn create_action(label: &str, start: Instant, offset: u64, duration: u64) -> Action {
+        let mut action = Action::new(NodeIndex::new(0));
+        action.label = Some(label.to_owned());
+        action.start_time = start + Duration::from_millis(offset);
+        action.duration = Some(Duration::from_millis(duration));
+        action.status = ActionStatus::Passed;
+        action
+    }
+
+    fn find_event<'a>(profile: &'a RunProfile, name: &str) -> &'a TraceEvent {
+        profile
+            .trace_events
+            .iter()
+            .find(|event| event.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn creates_complete_events_relative_to_start() {
+        let start = Instant::now();
+        let profile = RunProfile::new(&[create_action("SetupToolchain", start, 5, 10)], start);
+        let event = find_event(&profile, "SetupToolchain");
+
+        assert_eq!(event.ph, "X");
+        assert_eq!(event.ts, 5000);
+        assert_eq!(event.dur, Some(10000));
+        assert_eq!(event.args.get("status").unwrap(), "Passed");
+    }
+
+    #[test]
+    fn places_overlapping_actions_on_separate_workers() {
+        let start = Instant::now();
+        let profile = RunProfile::new(
+            &[
+                create_action("a", start, 0, 10),
+                create_action("b", start, 5, 10),
+                create_action("c", start, 12, 10),
+            ],
+            start,
+        );
+
+        assert_eq!(find_event(&profile, "a").tid, 1);
+        assert_eq!(find_event(&profile, "b").tid, 2);
+        assert_eq!(find_event(&profile, "c").tid, 1);
+
+        let threads = profile
+            .trace_events
+            .iter()
+            .filter(|event| event.name == "thread_name")
+            .count();
+
+        assert_eq!(threads, 2);
+    }
+
+    #[test]
+    fn includes_attempts_on_the_same_worker() {
+        let start = Instant::now();
+        let mut attempt = Attempt::new(1);
+        attempt.start_time = start + Duration::from_millis(2);
+        attempt.duration = Some(Duration::from_millis(5));
+        attempt.exit_code = Some(1);
+
+        let mut action = create_action("RunTarget(app:build)", start, 0, 10);
+        action.attempts = Some(vec![attempt]);
+
+        let profile = RunProfile::new(&[action], start);
+        let event = find_event(&profile, "Attempt 1");
+
+        assert_eq!(event.cat.as_deref(), Some("attempt"));
+        assert_eq!(event.tid, 1);
+        assert_eq!(event.ts, 2000);
+        assert_eq!(event.args.get("exitCode").unwrap(), "1");
+    }
+}
@@ -0,0 +1,156 @@
+use crate::action::{Action, ActionStatus, Attempt};
+use serde::Serialize;
+use std::time::Duration;
+
+fn to_millis(duration: Option<Duration>) -> Option<u64> {
+    duration.map(|d| d.as_millis() as u64)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttemptReport {
+    /// Duration of the attempt in milliseconds.
+    pub duration: Option<u64>,
+
+    pub exit_code: Option<i32>,
+
+    pub index: u8,
+
+    pub timed_out: bool,
+}
+
+impl AttemptReport {
+    pub fn new(attempt: &Attempt) -> Self {
+        AttemptReport {
+            duration: to_millis(attempt.duration),
+            exit_code: attempt.exit_code,
+            index: attempt.index,
+            timed_out: attempt.timed_out,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionReport {
+    pub attempts: Vec<AttemptReport>,
+
+    /// Whether the hash was found in the local or remote cache.
+    /// Is `None` for actions that are not hashed.
+    pub cache_hit: Option<bool>,
+
+    /// Duration of the action in milliseconds.
+    pub duration: Option<u64>,
+
+    pub error: Option<String>,
+
+    pub hash: Option<String>,
+
+    pub label: Option<String>,
+
+    pub status: ActionStatus,
+}
+
+impl ActionReport {
+    pub fn new(action: &Action) -> Self {
+        ActionReport {
+            attempts: action
+                .attempts
+                .iter()
+                .flatten()
+                .map(AttemptReport::new)
+                .collect(),
+            cache_hit: action.hash.as_ref().map(|_| action.was_cached()),
+            duration: to_millis(action.duration),
+            error: action.error.clone(),
+            hash: action.hash.clone(),
+            label: action.label.clone(),
+            status: action.status.clone(),
+        }
+    }
+}
+
+/// A machine-readable report of every action that was ran by the action runner,
+/// written to `.moon/cache/runReport.json` at the end of each run.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunReport {
+    pub actions: Vec<ActionReport>,
+
+    /// Duration of the entire run in milliseconds.
+    pub duration: u64,
+}
+
+impl RunReport {
+    pub fn new(actions: &[Action], duration: Duration) -> Self {
+        RunReport {
+            actions: actions.iter().map(ActionReport::new).collect(),
+            duration: duration.as_millis() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep_graph::NodeIndex;
+    use serde_json::json;
+
+    #[test]
+    fn serializes_actions() {
+        let mut attempt = Attempt::new(1);
+        attempt.duration = Some(Duration::from_millis(50));
+        attempt.exit_code = Some(1);
+
+        let mut action = Action::new(NodeIndex::new(1));
+        action.attempts = Some(vec![attempt]);
+        action.hash = Some(String::from("abc123"));
+        action.label = Some(String::from("RunTarget(app:build)"));
+        action.fail(String::from("Oops"));
+        action.duration = Some(Duration::from_millis(100));
+
+        let report = RunReport::new(&[action], Duration::from_millis(150));
+
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            json!({
+                "actions": [{
+                    "attempts": [{
+                        "duration": 50,
+                        "exitCode": 1,
+                        "index": 1,
+                        "timedOut": false,
+                    }],
+                    "cacheHit": false,
+                    "duration": 100,
+                    "error": "Oops",
+                    "hash": "abc123",
+                    "label": "RunTarget(app:build)",
+                    "status": "failed",
+                }],
+                "duration": 150,
+            })
+        );
+    }
+
+    #[test]
+    fn marks_cache_hits() {
+        let mut action = Action::new(NodeIndex::new(1));
+        action.hash = Some(String::from("abc123"));
+        action.pass(ActionStatus::CachedFromRemote);
+
+        let report = ActionReport::new(&action);
+
+        assert_eq!(report.cache_hit, Some(true));
+    }
+
+    #[test]
+    fn omits_cache_hit_for_unhashed_actions() {
+        let mut action = Action::new(NodeIndex::new(1));
+        action.pass(ActionStatus::Passed);
+
+        let report = ActionReport::new(&action);
+
+        assert_eq!(report.cache_hit, None);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::errors::WorkspaceError;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use moon_config::constants;
+use moon_logger::{color, debug, trace};
+use moon_project::TouchedFilePaths;
+use notify::{raw_watcher, RawEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc as std_mpsc;
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::time;
+
+const LOG_TARGET: &str = "moon:watcher";
+
+/// Folders that are written to by moon and package managers while running tasks,
+/// and should never trigger a re-run.
+const IGNORED_DIRS: [&str; 2] = [".git", "node_modules"];
+
+/// Watches the workspace for file changes, ignoring files that are ignored by git.
+pub struct FileWatcher {
+    cache_dir: PathBuf,
+
+    ignore: Option<Gitignore>,
+
+    /// Paths that are written to while running targets (their outputs).
+    ignored_paths: Vec<PathBuf>,
+
+    /// Files that have changed, but have not been returned yet.
+    pending: TouchedFilePaths,
+
+    receiver: UnboundedReceiver<PathBuf>,
+
+    root: PathBuf,
+
+    // Watching stops when dropped, so keep it alive with the struct
+    _watcher: RecommendedWatcher,
+}
+
+impl FileWatcher {
+    pub fn new(root: &Path) -> Result<Self, WorkspaceError> {
+        debug!(
+            target: LOG_TARGET,
+            "Watching {} for file changes",
+            color::path(root)
+        );
+
+        let mut ignore = None;
+        let ignore_path = root.join(".gitignore");
+
+        if ignore_path.exists() {
+            let mut builder = GitignoreBuilder::new(root);
+
+            if let Some(error) = builder.add(ignore_path) {
+                return Err(WorkspaceError::Ignore(error));
+            }
+
+            ignore = Some(builder.build()?);
+        }
+
+        let (raw_sender, raw_receiver) = std_mpsc::channel();
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut watcher = raw_watcher(raw_sender)?;
+
+        watcher.watch(root, RecursiveMode::Recursive)?;
+
+        // Events are sent through a std channel, so forward them
+        // from a thread to an async channel that can be awaited
+        thread::spawn(move || {
+            while let Ok(RawEvent { path, .. }) = raw_receiver.recv() {
+                if let Some(path) = path {
+                    if sender.send(path).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(FileWatcher {
+            cache_dir: root.join(constants::CONFIG_DIRNAME).join("cache"),
+            ignore,
+            ignored_paths: vec![],
+            pending: TouchedFilePaths::new(),
+            receiver,
+            root: root.to_path_buf(),
+            _watcher: watcher,
+        })
+    }
+
+    /// Wait for files to change, and return them once no other file has changed
+    /// within the debounce duration. Returns `None` if the watcher has stopped.
+    /// Changes are kept between calls, so this can be safely cancelled.
+    pub async fn next_changes(&mut self, debounce: Duration) -> Option<TouchedFilePaths> {
+        loop {
+            let path = if self.pending.is_empty() {
+                self.receiver.recv().await?
+            } else {
+                match time::timeout(debounce, self.receiver.recv()).await {
+                    Ok(Some(path)) => path,
+                    Ok(None) | Err(_) => return Some(mem::take(&mut self.pending)),
+                }
+            };
+
+            if self.is_ignored(&path) {
+                continue;
+            }
+
+            trace!(
+                target: LOG_TARGET,
+                "File {} has changed",
+                color::path(&path)
+            );
+
+            self.pending.insert(path);
+        }
+    }
+
+    /// Ignore changes to the provided paths (and their contents), for example, the outputs
+    /// of targets that are being ran, as writing them would otherwise trigger another run.
+    pub fn ignore_paths<I: IntoIterator<Item = PathBuf>>(&mut self, paths: I) {
+        for path in paths {
+            if !self.ignored_paths.contains(&path) {
+                self.ignored_paths.push(path);
+            }
+        }
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        if path.starts_with(&self.cache_dir)
+            || self
+                .ignored_paths
+                .iter()
+                .any(|ignored| path.starts_with(ignored))
+        {
+            return true;
+        }
+
+        let rel_path = match path.strip_prefix(&self.root) {
+            Ok(rel_path) => rel_path,
+            Err(_) => return true,
+        };
+
+        if rel_path
+            .components()
+            .any(|component| IGNORED_DIRS.iter().any(|dir| component.as_os_str() == *dir))
+        {
+            return true;
+        }
+
+        match &self.ignore {
+            Some(ignore) => ignore
+                .matched_path_or_any_parents(rel_path, path.is_dir())
+                .is_ignore(),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use moon_utils::test::create_fixtures_sandbox;
+
+    #[tokio::test]
+    async fn ignores_git_ignored_and_internal_files() {
+        let fixture = create_fixtures_sandbox("ignore");
+        let watcher = FileWatcher::new(fixture.path()).unwrap();
+
+        assert!(watcher.is_ignored(&fixture.path().join("bar")));
+        assert!(watcher.is_ignored(&fixture.path().join("dir/baz")));
+        assert!(watcher.is_ignored(&fixture.path().join(".moon/cache/runs/file.log")));
+        assert!(watcher.is_ignored(&fixture.path().join("app/node_modules/pkg/index.js")));
+        assert!(watcher.is_ignored(&PathBuf::from("/outside/file.js")));
+        assert!(!watcher.is_ignored(&fixture.path().join("foo")));
+        assert!(!watcher.is_ignored(&fixture.path().join("dir/qux")));
+    }
+
+    #[tokio::test]
+    async fn ignores_provided_paths() {
+        let fixture = create_fixtures_sandbox("ignore");
+        let mut watcher = FileWatcher::new(fixture.path()).unwrap();
+
+        watcher.ignore_paths(vec![
+            fixture.path().join("app/lib"),
+            fixture.path().join("app/index.d.ts"),
+        ]);
+
+        assert!(watcher.is_ignored(&fixture.path().join("app/lib")));
+        assert!(watcher.is_ignored(&fixture.path().join("app/lib/index.js")));
+        assert!(watcher.is_ignored(&fixture.path().join("app/index.d.ts")));
+        assert!(!watcher.is_ignored(&fixture.path().join("app/library.js")));
+        assert!(!watcher.is_ignored(&fixture.path().join("app/src/index.ts")));
+    }
+
+    #[tokio::test]
+    async fn debounces_changes() {
+        let fixture = create_fixtures_sandbox("ignore");
+        let mut watcher = FileWatcher::new(fixture.path()).unwrap();
+
+        std::fs::write(fixture.path().join("a.txt"), "a").unwrap();
+        std::fs::write(fixture.path().join("b.txt"), "b").unwrap();
+
+        let changes = watcher
+            .next_changes(Duration::from_millis(250))
+            .await
+            .unwrap();
+
+        assert!(changes.contains(&fixture.path().join("a.txt")));
+        assert!(changes.contains(&fixture.path().join("b.txt")));
+    }
+}
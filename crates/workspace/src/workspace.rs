@@ -1,6 +1,6 @@
 use crate::errors::WorkspaceError;
 use crate::vcs::{Vcs, VcsManager};
-use moon_cache::CacheEngine;
+use moon_cache::{CacheEngine, RemoteCache};
 use moon_config::package::PackageJson;
 use moon_config::tsconfig::TsConfigJson;
 use moon_config::{constants, format_errors, GlobalProjectConfig, WorkspaceConfig};
@@ -174,7 +174,12 @@ impl Workspace {
             load_tsconfig_json(&root_dir, &config.typescript.root_config_file_name).await?;
 
         // Setup components
-        let cache = CacheEngine::create(&root_dir).await?;
+        let mut cache = CacheEngine::create(&root_dir).await?;
+
+        if let Some(remote_cache_config) = &config.remote_cache {
+            cache.remote = Some(RemoteCache::new(remote_cache_config));
+        }
+
         let toolchain = Toolchain::create(&root_dir, &config).await?;
         let projects =
             ProjectGraph::create(&root_dir, project_config, &config.projects, &cache).await?;
@@ -8,15 +8,22 @@ export interface FileGroup {
 // Keep in sync with crates/project/src/task.rs
 export type TaskMergeStrategy = 'append' | 'prepend' | 'replace';
 
+export type TaskOutputStyle = 'buffer' | 'prefix' | 'stream';
+
 export interface TaskOptions {
+	cache: boolean;
 	mergeArgs: TaskMergeStrategy;
 	mergeDeps: TaskMergeStrategy;
 	mergeEnv: TaskMergeStrategy;
 	mergeInputs: TaskMergeStrategy;
 	mergeOutputs: TaskMergeStrategy;
+	mutex: string[];
+	outputStyle: TaskOutputStyle | null;
+	persistent: boolean;
 	retryCount: number;
 	runInCI: boolean;
 	runFromWorkspaceRoot: boolean;
+	timeout: number | null;
 }
 
 export interface Task {
@@ -27,6 +34,7 @@ export interface Task {
 	inputs: string[];
 	inputGlobs: string[];
 	inputPaths: string[];
+	inputVars: string[];
 	options: TaskOptions;
 	outputs: string[];
 	outputPaths: string[];
@@ -43,3 +51,56 @@ export interface Project {
 	source: string;
 	tasks: Record<string, Task>;
 }
+
+// Keep in sync with crates/workspace/src/run_report.rs
+export type ActionStatus =
+	| 'cached'
+	| 'cachedFromRemote'
+	| 'failed'
+	| 'failedAndAbort'
+	| 'interrupted'
+	| 'invalid'
+	| 'passed'
+	| 'running'
+	| 'skipped'
+	| 'skippedFromFailure';
+
+export interface AttemptReport {
+	duration: number | null;
+	exitCode: number | null;
+	index: number;
+	timedOut: boolean;
+}
+
+export interface ActionReport {
+	attempts: AttemptReport[];
+	cacheHit: boolean | null;
+	duration: number | null;
+	error: string | null;
+	hash: string | null;
+	label: string | null;
+	status: ActionStatus;
+}
+
+export interface RunReport {
+	actions: ActionReport[];
+	duration: number;
+}
+
+export interface PlannedCommand {
+	args: string[];
+	bin: string;
+	cwd: string | null;
+	env: Record<string, string>;
+}
+
+export interface PlannedAction {
+	cacheHit: boolean | null;
+	command: PlannedCommand | null;
+	hash: string | null;
+	label: string;
+}
+
+export interface RunPlan {
+	actions: PlannedAction[];
+}
@@ -0,0 +1,13 @@
+#!/usr/bin/env bash
+
+# Fails if another task holding the same resource is running
+if [ -f mutex.lock ]; then
+	echo "resource is already held"
+	exit 1
+fi
+
+touch mutex.lock
+sleep 1
+rm mutex.lock
+
+echo "released $1"
@@ -0,0 +1,8 @@
+#!/usr/bin/env bash
+
+echo "persistent started"
+
+# Never exits on its own, like a watcher or server
+while true; do
+	sleep 1
+done
@@ -0,0 +1,9 @@
+#!/usr/bin/env bash
+
+echo "stdout"
+
+# Spawn a child process, which should also be killed
+sleep 10 &
+sleep 10
+
+echo "This should not appear!"
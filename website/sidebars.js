@@ -82,7 +82,11 @@ const sidebars = {
 				'commands/overview',
 				'commands/bin',
 				'commands/ci',
+				'commands/clean',
+				'commands/dep-graph',
+				'commands/hash',
 				'commands/init',
+				'commands/log',
 				'commands/project',
 				'commands/project-graph',
 				'commands/run',